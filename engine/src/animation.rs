@@ -0,0 +1,81 @@
+use crate::sprite::Sprite;
+
+/// Cycles through sprite frames at a fixed rate. Draw the current frame
+/// with `Map::draw_sprite(origin, animation.frame(), id)`.
+pub struct Animation {
+    frames: Vec<Sprite>,
+    frame_time: f64,
+    looping: bool,
+    current: usize,
+    elapsed: f64,
+    finished: bool,
+}
+
+impl Animation {
+    /// An animation that loops forever, showing each frame for
+    /// `frame_time` seconds. Panics if `frames` is empty or `frame_time`
+    /// is not positive.
+    pub fn new(frames: Vec<Sprite>, frame_time: f64) -> Self {
+        assert!(!frames.is_empty(), "animation needs at least one frame");
+        assert!(frame_time > 0.0, "frame time must be positive");
+        Animation {
+            frames,
+            frame_time,
+            looping: true,
+            current: 0,
+            elapsed: 0.0,
+            finished: false,
+        }
+    }
+
+    /// An animation that plays once and then holds its last frame, e.g.
+    /// an explosion.
+    pub fn one_shot(frames: Vec<Sprite>, frame_time: f64) -> Self {
+        Animation {
+            looping: false,
+            ..Animation::new(frames, frame_time)
+        }
+    }
+
+    /// Advances the animation and returns true on the tick a one-shot
+    /// animation finishes.
+    pub fn tick(&mut self, delta: f64) -> bool {
+        if self.finished {
+            return false;
+        }
+        self.elapsed += delta;
+        while self.elapsed >= self.frame_time {
+            self.elapsed -= self.frame_time;
+            if self.current + 1 < self.frames.len() {
+                self.current += 1;
+            } else if self.looping {
+                self.current = 0;
+            } else {
+                self.finished = true;
+                return true;
+            }
+        }
+        false
+    }
+
+    pub fn frame(&self) -> &Sprite {
+        &self.frames[self.current]
+    }
+
+    pub fn frame_index(&self) -> usize {
+        self.current
+    }
+
+    /// Whether a one-shot animation has played through. Looping animations
+    /// never finish.
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    /// Starts over from the first frame.
+    pub fn reset(&mut self) {
+        self.current = 0;
+        self.elapsed = 0.0;
+        self.finished = false;
+    }
+}
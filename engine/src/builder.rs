@@ -0,0 +1,127 @@
+use crossterm::event::KeyCode;
+
+use crate::{
+    render::{CrosstermRenderer, Renderer},
+    World,
+};
+
+/// Configures a `World` in one chain, e.g.
+/// `World::builder().map(40, 20).target_fps(60.0).build()`. Options that
+/// aren't set keep `World::new`'s defaults; the map is 80 by 24 unless
+/// given.
+pub struct WorldBuilder {
+    map: (usize, usize),
+    renderer: Box<dyn FnOnce(usize, usize) -> World>,
+    target_fps: Option<f64>,
+    max_delta: Option<f64>,
+    fixed_timestep: Option<f64>,
+    seed: Option<u64>,
+    quit_key: Option<Option<KeyCode>>,
+    mouse: bool,
+}
+
+impl Default for WorldBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl WorldBuilder {
+    pub fn new() -> Self {
+        WorldBuilder {
+            map: (80, 24),
+            renderer: Box::new(|width, height| {
+                World::new_with_renderer(
+                    width,
+                    height,
+                    CrosstermRenderer::new(),
+                )
+            }),
+            target_fps: None,
+            max_delta: None,
+            fixed_timestep: None,
+            seed: None,
+            quit_key: None,
+            mouse: false,
+        }
+    }
+
+    pub fn map(mut self, width: usize, height: usize) -> Self {
+        self.map = (width, height);
+        self
+    }
+
+    /// Draws through `renderer` instead of the terminal; see
+    /// `World::new_with_renderer`.
+    pub fn renderer(mut self, renderer: impl Renderer) -> Self {
+        self.renderer = Box::new(move |width, height| {
+            World::new_with_renderer(width, height, renderer)
+        });
+        self
+    }
+
+    /// See `World::set_target_fps`.
+    pub fn target_fps(mut self, fps: f64) -> Self {
+        self.target_fps = Some(fps);
+        self
+    }
+
+    /// See `World::set_max_delta`.
+    pub fn max_delta(mut self, max: f64) -> Self {
+        self.max_delta = Some(max);
+        self
+    }
+
+    /// See `World::set_fixed_timestep`.
+    pub fn fixed_timestep(mut self, dt: f64) -> Self {
+        self.fixed_timestep = Some(dt);
+        self
+    }
+
+    /// See `World::seed_rng`.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// See `World::set_quit_key`; pass `None` for no quit key.
+    pub fn quit_key(mut self, key: impl Into<Option<KeyCode>>) -> Self {
+        self.quit_key = Some(key.into());
+        self
+    }
+
+    /// See `World::enable_mouse`.
+    pub fn enable_mouse(mut self, enabled: bool) -> Self {
+        self.mouse = enabled;
+        self
+    }
+
+    /// Creates the world. Panics on the same invalid values as the
+    /// setters it stands for.
+    pub fn build(self) -> World {
+        let mut world = (self.renderer)(self.map.0, self.map.1);
+        if let Some(fps) = self.target_fps {
+            world.set_target_fps(fps);
+        }
+        if let Some(max) = self.max_delta {
+            world.set_max_delta(max);
+        }
+        if let Some(dt) = self.fixed_timestep {
+            world.set_fixed_timestep(dt);
+        }
+        if let Some(seed) = self.seed {
+            world.seed_rng(seed);
+        }
+        if let Some(key) = self.quit_key {
+            world.set_quit_key(key);
+        }
+        world.enable_mouse(self.mouse);
+        world
+    }
+}
+
+impl World {
+    pub fn builder() -> WorldBuilder {
+        WorldBuilder::new()
+    }
+}
@@ -0,0 +1,50 @@
+use crate::{Entity, World};
+
+/// Several components set on an entity at once: a tuple of up to eight
+/// components, e.g. `(Health { hp: 10.0 }, Solid)`. A single component
+/// is a one-element tuple, `(Solid,)`.
+pub trait Bundle {
+    fn insert(self, world: &mut World, id: i64);
+}
+
+macro_rules! impl_bundle {
+    ($($component:ident),*) => {
+        impl<$($component: 'static),*> Bundle for ($($component,)*) {
+            #[allow(non_snake_case, unused_variables)]
+            fn insert(self, world: &mut World, id: i64) {
+                let ($($component,)*) = self;
+                $(world.set_component(id, $component);)*
+            }
+        }
+    };
+}
+
+impl_bundle!();
+impl_bundle!(A);
+impl_bundle!(A, B);
+impl_bundle!(A, B, C);
+impl_bundle!(A, B, C, D);
+impl_bundle!(A, B, C, D, E);
+impl_bundle!(A, B, C, D, E, F);
+impl_bundle!(A, B, C, D, E, F, G);
+impl_bundle!(A, B, C, D, E, F, G, H);
+
+impl World {
+    /// Adds an entity along with its components, which are in place by the
+    /// time its `start` runs. Returns the new entity's id.
+    pub fn add_entity_with(
+        &mut self,
+        entity: impl Entity + 'static,
+        components: impl Bundle,
+    ) -> i64 {
+        let id = self.next_id;
+        self.add_entity(entity);
+        components.insert(self, id);
+        id
+    }
+
+    /// Sets every component in `components` on an existing entity.
+    pub fn insert_bundle(&mut self, id: i64, components: impl Bundle) {
+        components.insert(self, id);
+    }
+}
@@ -0,0 +1,56 @@
+/// The part of the map that is drawn to the terminal. Map tiles inside the
+/// viewport are drawn with the camera's `position` at the terminal origin.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Camera {
+    /// The map tile shown in the top-left terminal cell.
+    pub position: (u16, u16),
+    /// The viewport size in terminal cells.
+    pub viewport: (u16, u16),
+}
+
+impl Camera {
+    pub fn new(viewport: (u16, u16)) -> Self {
+        Camera {
+            position: (0, 0),
+            viewport,
+        }
+    }
+
+    /// Moves the camera so `target` is in the middle of the viewport. The
+    /// world clamps the camera to the map before each draw, so near the
+    /// edges the target ends up off-center.
+    pub fn center_on(&mut self, target: (u16, u16)) {
+        self.position = (
+            target.0.saturating_sub(self.viewport.0 / 2),
+            target.1.saturating_sub(self.viewport.1 / 2),
+        );
+    }
+
+    /// Keeps the viewport inside a map of `map_size` tiles. A viewport
+    /// larger than the map is pinned to the map origin.
+    pub fn clamp_to(&mut self, map_size: (usize, usize)) {
+        let max_x = (map_size.0 as u16).saturating_sub(self.viewport.0);
+        let max_y = (map_size.1 as u16).saturating_sub(self.viewport.1);
+        self.position =
+            (self.position.0.min(max_x), self.position.1.min(max_y));
+    }
+
+    /// The terminal cell for a map tile, or `None` if it is out of view.
+    pub fn map_to_screen(&self, position: (u16, u16)) -> Option<(u16, u16)> {
+        let x = position.0.checked_sub(self.position.0)?;
+        let y = position.1.checked_sub(self.position.1)?;
+        if x < self.viewport.0 && y < self.viewport.1 {
+            Some((x, y))
+        } else {
+            None
+        }
+    }
+
+    /// The map tile shown in a terminal cell.
+    pub fn screen_to_map(&self, position: (u16, u16)) -> (u16, u16) {
+        (
+            position.0.saturating_add(self.position.0),
+            position.1.saturating_add(self.position.1),
+        )
+    }
+}
@@ -0,0 +1,118 @@
+use std::collections::HashSet;
+
+use serde::{Deserialize, Serialize};
+
+use crate::World;
+
+/// Collision filtering: an entity is told about a collision only when its
+/// `mask` shares a bit with the other entity's `layer`. Entities without a
+/// `CollisionLayer` are on every layer and collide with everything.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CollisionLayer {
+    pub layer: u32,
+    pub mask: u32,
+}
+
+impl Default for CollisionLayer {
+    fn default() -> Self {
+        CollisionLayer {
+            layer: u32::MAX,
+            mask: u32::MAX,
+        }
+    }
+}
+
+/// Marks an entity whose tiles block `World::try_move`, e.g. a wall.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct Solid;
+
+impl World {
+    /// Whether a `Solid` entity is on `position`, by what it wrote this
+    /// frame or last frame, or the tile was written with
+    /// `Map::write_solid`. Off-map positions are not solid.
+    pub fn is_solid(&self, position: (u16, u16)) -> bool {
+        self.solid_except(position, None)
+    }
+
+    /// Where entity `id` ends up moving from `from` toward `to`, stopped
+    /// short of solid tiles. Each axis is resolved on its own, horizontal
+    /// first, so a diagonal move into a wall slides along it. The entity's
+    /// own tiles never block it, unless it wrote them with `write_solid`.
+    pub fn try_move(
+        &self,
+        id: i64,
+        from: (u16, u16),
+        to: (u16, u16),
+    ) -> (u16, u16) {
+        let mut position = from;
+        if !self.solid_except((to.0, position.1), Some(id)) {
+            position.0 = to.0;
+        }
+        if !self.solid_except((position.0, to.1), Some(id)) {
+            position.1 = to.1;
+        }
+        position
+    }
+
+    pub(crate) fn solid_except(
+        &self,
+        position: (u16, u16),
+        id: Option<i64>,
+    ) -> bool {
+        self.map.is_solid(position)
+            || self
+                .entities_at_current(position)
+                .iter()
+                .chain(self.entities_at(position))
+                .any(|&x| Some(x) != id && self.has_component::<Solid>(x))
+    }
+
+    pub fn set_collision_layer(&mut self, id: i64, layer: u32, mask: u32) {
+        self.set_component(id, CollisionLayer { layer, mask });
+    }
+
+    fn collision_layer(&self, id: i64) -> CollisionLayer {
+        self.get_component_cloned(id).unwrap_or_default()
+    }
+
+    /// Calls `Entity::on_collision` on both entities of every pair sharing
+    /// a tile this frame, filtered by their `CollisionLayer`s. Each pair
+    /// fires once per frame even if it shares several tiles, and entities
+    /// already queued for removal are skipped.
+    pub(crate) fn detect_collisions(&mut self) {
+        let mut pairs = Vec::new();
+        let mut seen = HashSet::new();
+        for col in self.map.tiles.iter() {
+            for tile in col.iter() {
+                let ids = &tile.current_contents;
+                for (i, &a) in ids.iter().enumerate() {
+                    for &b in &ids[i + 1..] {
+                        let pair = (a.min(b), a.max(b));
+                        if a != b && seen.insert(pair) {
+                            pairs.push(pair);
+                        }
+                    }
+                }
+            }
+        }
+        for (a, b) in pairs {
+            if self.removal_queue.contains(&a)
+                || self.removal_queue.contains(&b)
+            {
+                continue;
+            }
+            let (layer_a, layer_b) =
+                (self.collision_layer(a), self.collision_layer(b));
+            if layer_a.mask & layer_b.layer != 0 {
+                self.with_entity_taken(a, |entity, world| {
+                    entity.on_collision(b, world, a)
+                });
+            }
+            if layer_b.mask & layer_a.layer != 0 {
+                self.with_entity_taken(b, |entity, world| {
+                    entity.on_collision(a, world, b)
+                });
+            }
+        }
+    }
+}
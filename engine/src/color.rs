@@ -0,0 +1,149 @@
+use std::{error::Error, fmt};
+
+use crossterm::style::Color;
+
+pub fn rgb(r: u8, g: u8, b: u8) -> Color {
+    Color::Rgb { r, g, b }
+}
+
+/// A color from the 256-color palette. Works on terminals without
+/// truecolor support.
+pub fn ansi256(n: u8) -> Color {
+    Color::AnsiValue(n)
+}
+
+/// A hex string that `from_hex` could not read.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseColorError {
+    pub input: String,
+}
+
+impl fmt::Display for ParseColorError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?} is not a #rrggbb or #rgb color", self.input)
+    }
+}
+
+impl Error for ParseColorError {}
+
+/// Parses `#rrggbb` or `#rgb`; the leading `#` is optional.
+pub fn from_hex(hex: &str) -> Result<Color, ParseColorError> {
+    let error = || ParseColorError {
+        input: hex.to_string(),
+    };
+    let digits = hex.strip_prefix('#').unwrap_or(hex);
+    // `from_str_radix` would also take a sign, as in "+f".
+    if !digits.chars().all(|x| x.is_ascii_hexdigit()) {
+        return Err(error());
+    }
+    let channel = |s: &str| u8::from_str_radix(s, 16).map_err(|_| error());
+    match digits.len() {
+        6 => Ok(rgb(
+            channel(&digits[0..2])?,
+            channel(&digits[2..4])?,
+            channel(&digits[4..6])?,
+        )),
+        3 => Ok(rgb(
+            channel(&digits[0..1])? * 17,
+            channel(&digits[1..2])? * 17,
+            channel(&digits[2..3])? * 17,
+        )),
+        _ => Err(error()),
+    }
+}
+
+/// The RGB value of a color, using xterm's defaults for the named and
+/// 256-color palettes. `Color::Reset` has no fixed value.
+pub fn to_rgb(color: Color) -> Option<(u8, u8, u8)> {
+    let rgb = match color {
+        Color::Reset => return None,
+        Color::Rgb { r, g, b } => (r, g, b),
+        Color::Black => (0, 0, 0),
+        Color::DarkRed => (128, 0, 0),
+        Color::DarkGreen => (0, 128, 0),
+        Color::DarkYellow => (128, 128, 0),
+        Color::DarkBlue => (0, 0, 128),
+        Color::DarkMagenta => (128, 0, 128),
+        Color::DarkCyan => (0, 128, 128),
+        Color::Grey => (192, 192, 192),
+        Color::DarkGrey => (128, 128, 128),
+        Color::Red => (255, 0, 0),
+        Color::Green => (0, 255, 0),
+        Color::Yellow => (255, 255, 0),
+        Color::Blue => (0, 0, 255),
+        Color::Magenta => (255, 0, 255),
+        Color::Cyan => (0, 255, 255),
+        Color::White => (255, 255, 255),
+        Color::AnsiValue(n) => return ansi256_to_rgb(n),
+    };
+    Some(rgb)
+}
+
+fn ansi256_to_rgb(n: u8) -> Option<(u8, u8, u8)> {
+    const NAMED: [Color; 16] = [
+        Color::Black,
+        Color::DarkRed,
+        Color::DarkGreen,
+        Color::DarkYellow,
+        Color::DarkBlue,
+        Color::DarkMagenta,
+        Color::DarkCyan,
+        Color::Grey,
+        Color::DarkGrey,
+        Color::Red,
+        Color::Green,
+        Color::Yellow,
+        Color::Blue,
+        Color::Magenta,
+        Color::Cyan,
+        Color::White,
+    ];
+    match n {
+        0..=15 => to_rgb(NAMED[n as usize]),
+        16..=231 => {
+            let level = |x: u8| if x == 0 { 0 } else { 55 + x * 40 };
+            let n = n - 16;
+            Some((level(n / 36), level(n / 6 % 6), level(n % 6)))
+        }
+        232..=255 => {
+            let grey = 8 + (n - 232) * 10;
+            Some((grey, grey, grey))
+        }
+    }
+}
+
+/// Blends from `a` at `t = 0.0` to `b` at `t = 1.0` in RGB space; `t` is
+/// clamped to that range. If either color has no RGB value
+/// (`Color::Reset`), switches from `a` to `b` halfway instead.
+pub fn lerp(a: Color, b: Color, t: f64) -> Color {
+    let t = t.clamp(0.0, 1.0);
+    match (to_rgb(a), to_rgb(b)) {
+        (Some(a), Some(b)) => {
+            let mix = |a: u8, b: u8| {
+                (a as f64 + (b as f64 - a as f64) * t).round() as u8
+            };
+            rgb(mix(a.0, b.0), mix(a.1, b.1), mix(a.2, b.2))
+        }
+        _ => {
+            if t < 0.5 {
+                a
+            } else {
+                b
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{from_hex, rgb};
+
+    #[test]
+    fn from_hex_reads_only_hex_digits() {
+        assert_eq!(from_hex("#ff8000"), Ok(rgb(255, 128, 0)));
+        assert_eq!(from_hex("f80"), Ok(rgb(255, 136, 0)));
+        for bad in ["+f+f+f", "#+ff000", "#-1", "#12345g", "#ééé"] {
+            assert!(from_hex(bad).is_err(), "{bad}");
+        }
+    }
+}
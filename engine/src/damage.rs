@@ -0,0 +1,80 @@
+use serde::{Deserialize, Serialize};
+
+use crate::World;
+
+/// Hit points. Entities with `Health` take damage sent with
+/// `World::damage` and are removed when it runs out.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Health {
+    pub hp: f64,
+    pub max: f64,
+    /// Seconds of `Invulnerable` granted after each hit that doesn't kill,
+    /// 0 for none.
+    pub grace: f64,
+}
+
+impl Health {
+    pub fn new(max: f64) -> Self {
+        Health {
+            hp: max,
+            max,
+            grace: 0.0,
+        }
+    }
+
+    pub fn with_grace(self, seconds: f64) -> Self {
+        Health {
+            grace: seconds,
+            ..self
+        }
+    }
+
+    /// `hp` as a fraction of `max`, e.g. for `UI::draw_bar`.
+    pub fn fraction(&self) -> f64 {
+        if self.max > 0.0 {
+            self.hp / self.max
+        } else {
+            0.0
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DamageEvent {
+    pub target_id: i64,
+    pub amount: f64,
+}
+
+impl World {
+    /// Queues `amount` of damage for the entity. Damage is applied once
+    /// per update pass, after collisions, so `on_collision` can send it:
+    /// it comes off the target's `Health`, is ignored while the target is
+    /// `Invulnerable`, and removes the target when its hp reaches 0, which
+    /// runs its `end` as usual. Entities without `Health` aren't hurt.
+    pub fn damage(&mut self, target_id: i64, amount: f64) {
+        self.send_damage(DamageEvent { target_id, amount });
+    }
+
+    pub fn send_damage(&mut self, event: DamageEvent) {
+        self.damage_events.push(event);
+    }
+
+    pub(crate) fn apply_damage(&mut self) {
+        for event in std::mem::take(&mut self.damage_events) {
+            let id = event.target_id;
+            if self.removal_queue.contains(&id) || self.is_invulnerable(id) {
+                continue;
+            }
+            let Some(health) = self.get_component::<Health>(id) else {
+                continue;
+            };
+            health.hp -= event.amount;
+            let (hp, grace) = (health.hp, health.grace);
+            if hp <= 0.0 {
+                self.remove_entity(id);
+            } else if grace > 0.0 {
+                self.grant_invulnerability(id, grace);
+            }
+        }
+    }
+}
@@ -0,0 +1,82 @@
+use serde::{Deserialize, Serialize};
+
+use crate::World;
+
+/// Which way an entity points. Stored as a component with
+/// `World::set_facing`, then turned into a glyph or sprite with
+/// `Directional`.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize,
+)]
+pub enum Facing {
+    #[default]
+    Up,
+    Down,
+    Left,
+    Right,
+}
+
+impl Facing {
+    /// The facing of a step or target direction, horizontal winning over
+    /// vertical for diagonals. `None` for `(0, 0)`.
+    pub fn from_direction(direction: (i8, i8)) -> Option<Facing> {
+        match direction {
+            (x, _) if x < 0 => Some(Facing::Left),
+            (x, _) if x > 0 => Some(Facing::Right),
+            (_, y) if y < 0 => Some(Facing::Up),
+            (_, y) if y > 0 => Some(Facing::Down),
+            _ => None,
+        }
+    }
+
+    /// A one tile step this way; up is toward row 0.
+    pub fn direction(self) -> (i8, i8) {
+        match self {
+            Facing::Up => (0, -1),
+            Facing::Down => (0, 1),
+            Facing::Left => (-1, 0),
+            Facing::Right => (1, 0),
+        }
+    }
+}
+
+/// One value per facing, e.g. the glyphs `'^'`, `'v'`, `'<'` and `'>'` or
+/// four sprites.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Directional<T> {
+    pub up: T,
+    pub down: T,
+    pub left: T,
+    pub right: T,
+}
+
+impl<T> Directional<T> {
+    pub fn new(up: T, down: T, left: T, right: T) -> Self {
+        Directional {
+            up,
+            down,
+            left,
+            right,
+        }
+    }
+
+    pub fn get(&self, facing: Facing) -> &T {
+        match facing {
+            Facing::Up => &self.up,
+            Facing::Down => &self.down,
+            Facing::Left => &self.left,
+            Facing::Right => &self.right,
+        }
+    }
+}
+
+impl World {
+    pub fn set_facing(&mut self, id: i64, facing: Facing) {
+        self.set_component(id, facing);
+    }
+
+    /// The entity's `Facing`, or `None` if it was never set.
+    pub fn facing(&self, id: i64) -> Option<Facing> {
+        self.get_component_cloned::<Facing>(id)
+    }
+}
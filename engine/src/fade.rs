@@ -0,0 +1,82 @@
+use crate::World;
+
+impl World {
+    /// Dissolves the map to blank over `duration` seconds, cell by cell in
+    /// a fixed scattered order, then keeps it blank until `fade_in`. The
+    /// game keeps running underneath, e.g. to swap levels once the screen
+    /// is dark:
+    /// `world.fade_out(0.5); world.schedule(0.5, |w| { w.restart();
+    /// w.fade_in(0.5) })`. Fades advance with each frame's delta, also
+    /// while paused. The FPS overlay is never faded.
+    pub fn fade_out(&mut self, duration: f64) {
+        self.start_fade(1.0, duration);
+    }
+
+    /// Brings the map back from a `fade_out` over `duration` seconds.
+    pub fn fade_in(&mut self, duration: f64) {
+        self.start_fade(0.0, duration);
+    }
+
+    /// Whether a fade is still in progress.
+    pub fn is_fading(&self) -> bool {
+        self.fade_rate != 0.0
+    }
+
+    fn start_fade(&mut self, target: f64, duration: f64) {
+        let distance = target - self.fade_level;
+        if duration > 0.0 {
+            self.fade_rate = distance / duration;
+        } else {
+            self.fade_level = target;
+            self.fade_rate = 0.0;
+            self.drawn_camera = None;
+        }
+    }
+
+    pub(crate) fn update_fade(&mut self, delta: f64) {
+        if self.fade_rate == 0.0 {
+            return;
+        }
+        self.fade_level += self.fade_rate * delta;
+        if !(0.0..1.0).contains(&self.fade_level) {
+            self.fade_level = self.fade_level.clamp(0.0, 1.0);
+            self.fade_rate = 0.0;
+        }
+        // Faded cells aren't dirty tiles, so look at every cell.
+        self.drawn_camera = None;
+    }
+
+    // Whether the screen cell at `position` is blanked by the fade. Each
+    // cell has a fixed threshold, so cells drop out in the same order
+    // every time and stay out as the fade deepens.
+    pub(crate) fn is_faded(&self, position: (u16, u16)) -> bool {
+        if self.fade_level <= 0.0 {
+            return false;
+        }
+        // The threshold below can reach 1.0, which `<` would never pass.
+        if self.fade_level >= 1.0 {
+            return true;
+        }
+        let mut h = (position.0 as u32).wrapping_mul(0x9e37_79b1)
+            ^ (position.1 as u32).wrapping_mul(0x85eb_ca77);
+        h ^= h >> 15;
+        h = h.wrapping_mul(0x2c1b_3c6d);
+        h ^= h >> 12;
+        (h as f64 / u32::MAX as f64) < self.fade_level
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{render::BufferRenderer, World};
+
+    #[test]
+    fn full_fade_blanks_every_cell() {
+        let mut world =
+            World::new_with_renderer(1, 1, BufferRenderer::new(1, 1));
+        let cells = || (0..256).flat_map(|x| (0..256).map(move |y| (x, y)));
+        assert!(!cells().any(|x| world.is_faded(x)));
+        world.fade_out(0.0);
+        assert!(cells().all(|x| world.is_faded(x)));
+    }
+}
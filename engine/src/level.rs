@@ -0,0 +1,119 @@
+use std::{error::Error, fmt, fs, io, path::Path};
+
+use crate::{Entity, World};
+
+/// Lines of a level template starting with this are skipped.
+pub const COMMENT: char = ';';
+
+#[derive(Debug)]
+pub enum LevelError {
+    Io(io::Error),
+    /// A row whose width differs from the first row's. `line` counts from
+    /// 1 and includes comment and blank lines.
+    RaggedRow {
+        line: usize,
+        expected: usize,
+        found: usize,
+    },
+    /// A template with no rows left once blank and comment lines are
+    /// skipped, which would leave no map to play on.
+    Empty,
+}
+
+impl fmt::Display for LevelError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LevelError::Io(error) => write!(f, "could not read level: {error}"),
+            LevelError::RaggedRow {
+                line,
+                expected,
+                found,
+            } => write!(
+                f,
+                "line {line} is {found} characters wide, expected {expected}"
+            ),
+            LevelError::Empty => write!(f, "level has no rows"),
+        }
+    }
+}
+
+impl Error for LevelError {}
+
+impl From<io::Error> for LevelError {
+    fn from(error: io::Error) -> Self {
+        LevelError::Io(error)
+    }
+}
+
+impl World {
+    /// Builds a level from ASCII art. Each character is passed to
+    /// `factory` with its tile position, and any entity it returns is
+    /// added. Blank lines and lines starting with `COMMENT` are skipped.
+    /// The rest must all be the same width, and the map is resized to fit
+    /// them. Nothing is spawned and the map is left alone if the template is
+    /// invalid or has no rows.
+    pub fn load_level(
+        &mut self,
+        template: &str,
+        factory: impl Fn(char, (u16, u16)) -> Option<Box<dyn Entity>>,
+    ) -> Result<(), LevelError> {
+        let mut rows: Vec<Vec<char>> = Vec::new();
+        for (i, line) in template.lines().enumerate() {
+            if line.is_empty() || line.starts_with(COMMENT) {
+                continue;
+            }
+            let row: Vec<char> = line.chars().collect();
+            if let Some(first) = rows.first() {
+                if row.len() != first.len() {
+                    return Err(LevelError::RaggedRow {
+                        line: i + 1,
+                        expected: first.len(),
+                        found: row.len(),
+                    });
+                }
+            }
+            rows.push(row);
+        }
+
+        let Some(width) = rows.first().map(Vec::len) else {
+            return Err(LevelError::Empty);
+        };
+        self.map.resize(width, rows.len());
+        for (y, row) in rows.iter().enumerate() {
+            for (x, character) in row.iter().enumerate() {
+                if let Some(entity) = factory(*character, (x as u16, y as u16))
+                {
+                    self.add_boxed_entity(entity);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// `load_level` with the template read from a file.
+    pub fn load_level_file(
+        &mut self,
+        path: impl AsRef<Path>,
+        factory: impl Fn(char, (u16, u16)) -> Option<Box<dyn Entity>>,
+    ) -> Result<(), LevelError> {
+        let template = fs::read_to_string(path)?;
+        self.load_level(&template, factory)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::LevelError;
+    use crate::{render::BufferRenderer, World};
+
+    #[test]
+    fn empty_level_is_an_error() {
+        let mut world =
+            World::new_with_renderer(4, 3, BufferRenderer::new(4, 3));
+        for template in ["", "; just a comment\n\n;another"] {
+            let result = world.load_level(template, |_, _| None);
+            assert!(matches!(result, Err(LevelError::Empty)));
+        }
+        assert_eq!(world.map_size(), (4, 3));
+    }
+}
@@ -1,30 +1,118 @@
-use crossterm::{
-    cursor::{Hide, MoveTo, Show},
-    event::KeyCode,
-    style::Color,
-    terminal, ExecutableCommand,
-};
-use ui::UI;
+pub use builder::WorldBuilder;
+pub use bundle::Bundle;
+use camera::Camera;
+pub use collision::{CollisionLayer, Solid};
+use crossterm::event::KeyCode;
+pub use damage::{DamageEvent, Health};
+pub use facing::{Directional, Facing};
+pub use map::{Map, MapTile, OutOfBounds, WrapMode};
+use palette::Palette;
+use render::{CrosstermRenderer, Renderer};
+use replay::{RecordedFrame, Recording};
+use rng::Rng;
+use schedule::ScheduledTask;
+pub use schedule::TaskHandle;
+use serde::{Deserialize, Serialize};
+use shake::Shake;
+use style::Style;
+pub use system::System;
+use ui::{input::InputMap, UI};
+pub use vec2::Vec2;
 //use space_invaders_macros::Component;
 use std::{
     any::{Any, TypeId},
-    collections::HashMap,
+    collections::{BTreeMap, HashMap, HashSet, VecDeque},
     io::{self, Write},
+    mem,
+    rc::Rc,
     thread,
     time::{Duration, Instant},
 };
+pub mod animation;
+mod builder;
+mod bundle;
+pub mod camera;
+mod collision;
+pub mod color;
+mod damage;
+mod facing;
+mod fade;
+pub mod level;
+pub mod map;
+pub mod movement;
+pub mod palette;
+pub mod pathfinding;
+pub mod render;
+pub mod replay;
+pub mod rng;
+pub mod save;
+mod schedule;
+mod shake;
+pub mod sprite;
+pub mod style;
+mod system;
+mod tag;
+#[cfg(test)]
+mod tests;
+pub mod timer;
 pub mod ui;
-// Drawing too fast causes flickering
+pub mod unicode;
+pub mod vec2;
+// The default frame time cap (25 FPS); see `World::set_target_fps`
 const MIN_FRAME_TIME: f64 = 0.04;
-pub trait Entity {
-    fn start(&mut self, world: &mut World, id: i64) {}
-    fn update(&mut self, delta: f64, world: &mut World, id: i64) {}
+// The default longest frame simulated; see `World::set_max_delta`
+const MAX_DELTA: f64 = 0.1;
+// How many recent frames `World::fps` averages over
+const FPS_SAMPLES: usize = 30;
+// How long a flashing `Invulnerable` entity stays shown, then hidden
+const INVULNERABLE_FLASH_TIME: f64 = 0.1;
+// Marks a screen cell covered by the wide character to its left
+const WIDE_TAIL: char = '\0';
+pub trait Entity: Any {
+    fn start(&mut self, _world: &mut World, _id: i64) {}
+    fn update(&mut self, _delta: f64, _world: &mut World, _id: i64) {}
+    /// Writes the entity to the map. Runs after every entity's `update`,
+    /// the systems and scheduled tasks, and before collisions are found,
+    /// so what is drawn here collides like writes made in `update`. Leave
+    /// it out to keep drawing from `update` instead.
+    fn draw(&self, _map: &mut Map, _id: i64) {}
+    /// Runs once, when a `remove_entity` call takes effect and just before
+    /// the entity and its components are dropped. Entities spawned here
+    /// join the update pass that follows.
+    fn end(&mut self, _world: &mut World, _id: i64) {}
+    /// Runs after the frame's updates for each other entity that wrote to
+    /// a tile this entity also wrote to.
+    fn on_collision(&mut self, _other_id: i64, _world: &mut World, _id: i64) {}
 }
 
+type Setup = Rc<dyn Fn(&mut World)>;
+
+/// Stands in for an entity while it is taken out of its slot to update.
+struct Vacant;
+
+impl Entity for Vacant {}
+
 pub struct EntityData {
     pub entity: Box<dyn Entity>,
     pub id: i64,
     started: bool,
+    z_index: i32,
+    active: bool,
+    frozen: bool,
+}
+
+impl EntityData {
+    pub fn z_index(&self) -> i32 {
+        self.z_index
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+
+    pub fn is_frozen(&self) -> bool {
+        self.frozen
+    }
 }
 
 pub struct World {
@@ -32,146 +120,998 @@ pub struct World {
     removal_queue: Vec<i64>,
     pub map: Map,
     pub ui: UI,
+    pub input_map: InputMap,
+    pub camera: Camera,
+    /// What each viewport cell currently shows on the terminal, or `None`
+    /// if unknown.
+    screen: Vec<Vec<Option<(char, Style)>>>,
+    // The camera position, overlay cells and cells left to the UI that
+    // `screen` was drawn with. `None` means `screen` can't be trusted and
+    // every cell is redrawn.
+    drawn_camera: Option<(i32, i32)>,
+    drawn_overlay: Vec<(u16, u16)>,
+    drawn_ui: Vec<(u16, u16)>,
+    // The map's size at the last draw, to notice it being resized.
+    drawn_map_size: (usize, usize),
     next_id: i64,
-    components: HashMap<i64, HashMap<String, Box<dyn Any>>>,
+    min_frame_time: f64,
+    max_delta: f64,
+    fixed_timestep: Option<f64>,
+    accumulated_time: f64,
+    paused: bool,
+    exit: Option<ExitReason>,
+    frame_count: u64,
+    elapsed: f64,
+    frame_times: VecDeque<f64>,
+    // Where the FPS overlay is drawn, if shown; `None` inside means the
+    // top-right corner.
+    fps_overlay: Option<Option<(u16, u16)>>,
+    tasks: Vec<ScheduledTask>,
+    cancelled_tasks: Vec<TaskHandle>,
+    next_task_id: u64,
+    rng: Rng,
+    systems: Vec<Box<dyn System>>,
+    // Set when `entities` may no longer be sorted by z-index.
+    order_dirty: bool,
+    tags: HashMap<String, Vec<i64>>,
+    recording: Option<Recording>,
+    playback: VecDeque<RecordedFrame>,
+    setup: Option<Setup>,
+    restart_pending: bool,
+    quit_key: Option<KeyCode>,
+    shake: Option<Shake>,
+    shake_offset: (i16, i16),
+    // 0 shows the map, 1 hides it all; see `World::fade_out`.
+    fade_level: f64,
+    fade_rate: f64,
+    damage_events: Vec<DamageEvent>,
+    components: BTreeMap<i64, HashMap<TypeId, Box<dyn Any>>>,
 }
 
 impl World {
+    /// A world drawing to the terminal. Panics if either map dimension is
+    /// zero.
     pub fn new(map_width: usize, map_height: usize) -> Self {
+        World::new_with_renderer(
+            map_width,
+            map_height,
+            CrosstermRenderer::new(),
+        )
+    }
+
+    /// A world that draws to `output` instead of stdout, through the usual
+    /// terminal renderer.
+    pub fn new_with_output(
+        map_width: usize,
+        map_height: usize,
+        output: impl Write + 'static,
+    ) -> Self {
+        World::new_with_renderer(
+            map_width,
+            map_height,
+            CrosstermRenderer::with_output(output),
+        )
+    }
+
+    /// A world that draws through `renderer` instead of the terminal, e.g.
+    /// a `BufferRenderer` for headless tests.
+    pub fn new_with_renderer(
+        map_width: usize,
+        map_height: usize,
+        renderer: impl Renderer,
+    ) -> Self {
         World {
             entities: Vec::new(),
             map: Map::new(map_width, map_height),
-            ui: UI::new(),
+            ui: UI::with_renderer(Box::new(renderer)),
+            input_map: InputMap::new(),
+            camera: Camera::new((map_width as u16, map_height as u16)),
+            screen: Vec::new(),
+            drawn_camera: None,
+            drawn_overlay: Vec::new(),
+            drawn_ui: Vec::new(),
+            drawn_map_size: (map_width, map_height),
             next_id: 0,
+            min_frame_time: MIN_FRAME_TIME,
+            max_delta: MAX_DELTA,
+            fixed_timestep: None,
+            accumulated_time: 0.0,
+            paused: false,
+            exit: None,
+            frame_count: 0,
+            elapsed: 0.0,
+            frame_times: VecDeque::new(),
+            fps_overlay: None,
+            tasks: Vec::new(),
+            cancelled_tasks: Vec::new(),
+            next_task_id: 0,
+            rng: Rng::default(),
+            systems: Vec::new(),
+            order_dirty: false,
+            tags: HashMap::new(),
+            recording: None,
+            playback: VecDeque::new(),
+            setup: None,
+            restart_pending: false,
+            quit_key: Some(KeyCode::Char('q')),
+            shake: None,
+            shake_offset: (0, 0),
+            fade_level: 0.0,
+            fade_rate: 0.0,
+            damage_events: Vec::new(),
             removal_queue: vec![],
-            components: HashMap::new(),
+            components: BTreeMap::new(),
         }
     }
 
+    /// Adds an entity. One added during an update pass, from any `start`,
+    /// `update` or system, waits for the next pass: its `start` runs then,
+    /// just before its first `update`. Use `spawn_immediate` to run
+    /// `start` straight away.
     pub fn add_entity(&mut self, entity_data: impl Entity + 'static) {
+        self.add_boxed_entity(Box::new(entity_data));
+    }
+
+    /// Adds an entity and runs its `start` now, e.g. so a spawner can set
+    /// up children's components in the same frame. Its first `update` is
+    /// still in the next pass. Returns the new entity's id.
+    pub fn spawn_immediate(&mut self, entity: impl Entity + 'static) -> i64 {
+        let id = self.next_id;
+        self.add_entity(entity);
+        if let Some(x) = self.get_entity_mut(id) {
+            x.started = true;
+        }
+        self.with_entity_taken(id, |entity, world| entity.start(world, id));
+        id
+    }
+
+    pub fn add_boxed_entity(&mut self, entity: Box<dyn Entity>) {
         self.entities.push(EntityData {
-            entity: Box::new(entity_data),
+            entity,
             id: self.next_id,
             started: false,
+            z_index: 0,
+            active: true,
+            frozen: false,
         });
+        self.order_dirty = true;
         self.components.insert(self.next_id, HashMap::new());
         self.next_id += 1;
     }
 
+    /// Sets the order entities update in and draw in: higher z-indices
+    /// update later and are drawn over lower ones sharing a tile. Entities
+    /// start at 0, and ties keep the order they were added in. Takes effect
+    /// from the next update pass.
+    pub fn set_z_index(&mut self, id: i64, z_index: i32) {
+        if let Some(x) = self.get_entity_mut(id) {
+            x.z_index = z_index;
+            self.map.z_indices.insert(id, z_index);
+            self.order_dirty = true;
+        }
+    }
+
+    /// Removes every entity, component, tag, scheduled task and pending
+    /// damage and blanks the map, keeping the terminal, systems, input
+    /// bindings and settings, e.g. between levels. `end` is not called on
+    /// the removed entities. With `reset_ids`, ids start again from 0, so
+    /// ids kept from before the clear may then name new entities;
+    /// otherwise ids are never reused.
+    pub fn clear(&mut self, reset_ids: bool) {
+        self.entities.clear();
+        self.removal_queue.clear();
+        self.components.clear();
+        self.tags.clear();
+        self.tasks.clear();
+        self.cancelled_tasks.clear();
+        self.damage_events.clear();
+        self.accumulated_time = 0.0;
+        self.order_dirty = false;
+        let wrap_mode = self.map.wrap_mode;
+        self.map = Map::new(self.map.width(), self.map.height());
+        self.map.wrap_mode = wrap_mode;
+        // The new map has no dirty tiles, yet everything on screen is stale.
+        self.map.resized = true;
+        if reset_ids {
+            self.next_id = 0;
+        }
+    }
+
+    /// Stops or resumes an entity's `start` and `update` without removing
+    /// it: an inactive entity stays queryable and keeps its components,
+    /// but writes nothing to the map, so it also doesn't collide.
+    pub fn set_active(&mut self, id: i64, active: bool) {
+        if let Some(x) = self.get_entity_mut(id) {
+            x.active = active;
+        }
+    }
+
+    /// Freezes or thaws an entity: a frozen entity skips `start` and
+    /// `update` but still runs `Entity::draw`, so it stays on screen and
+    /// collides, e.g. a boss held still during a cutscene. Whatever it drew
+    /// from `update` is gone, since that no longer runs. Freezing also
+    /// holds a `RunWhilePaused` entity still while paused; the global pause
+    /// stops everything else whether frozen or not.
+    pub fn freeze(&mut self, id: i64, frozen: bool) {
+        if let Some(x) = self.get_entity_mut(id) {
+            x.frozen = frozen;
+        }
+    }
+
+    /// Hides or shows an entity while it keeps updating. A hidden entity's
+    /// map writes still occupy tiles, for queries and collisions, but
+    /// leave the tile's character and style alone.
+    pub fn set_visible(&mut self, id: i64, visible: bool) {
+        if visible {
+            self.map.hidden.remove(&id);
+        } else if self.get_entity(id).is_some() {
+            self.map.hidden.insert(id);
+        }
+    }
+
     pub fn remove_entity(&mut self, id: i64) {
         self.removal_queue.push(id);
     }
 
+    /// Looks up an entity by id. Entities queued with `remove_entity` stay
+    /// visible until the removal is processed. While an entity is running
+    /// its own `start` or `update`, its slot holds a placeholder.
+    pub fn get_entity(&self, id: i64) -> Option<&EntityData> {
+        self.entities.iter().find(|x| x.id == id)
+    }
+
+    pub fn get_entity_mut(&mut self, id: i64) -> Option<&mut EntityData> {
+        self.entities.iter_mut().find(|x| x.id == id)
+    }
+
+    /// Looks up an entity and downcasts it to its concrete type. Returns
+    /// `None` if the id is unknown or the entity is not a `T`.
+    pub fn get_entity_as<T: Entity>(&self, id: i64) -> Option<&T> {
+        let entity: &dyn Any = self.get_entity(id)?.entity.as_ref();
+        entity.downcast_ref::<T>()
+    }
+
+    pub fn get_entity_as_mut<T: Entity>(&mut self, id: i64) -> Option<&mut T> {
+        let entity: &mut dyn Any = self.get_entity_mut(id)?.entity.as_mut();
+        entity.downcast_mut::<T>()
+    }
+
+    /// Every entity that is a `T`, with its id, in update order. An entity
+    /// in the middle of its own `start` or `update` is skipped, since its
+    /// slot holds a placeholder.
+    pub fn iter_type<T: Entity>(&self) -> impl Iterator<Item = (i64, &T)> {
+        self.entities.iter().filter_map(|x| {
+            let entity: &dyn Any = x.entity.as_ref();
+            entity.downcast_ref::<T>().map(|entity| (x.id, entity))
+        })
+    }
+
+    pub fn iter_type_mut<T: Entity>(
+        &mut self,
+    ) -> impl Iterator<Item = (i64, &mut T)> {
+        self.entities.iter_mut().filter_map(|x| {
+            let entity: &mut dyn Any = x.entity.as_mut();
+            entity.downcast_mut::<T>().map(|entity| (x.id, entity))
+        })
+    }
+
+    /// Caps the game loop at `fps` frames per second. The loop sleeps off
+    /// whatever is left of each frame rather than busy-waiting, so a
+    /// frame that takes longer than `1 / fps` just runs slower. Panics if
+    /// `fps` is not a positive number.
+    pub fn set_target_fps(&mut self, fps: f64) {
+        assert!(fps > 0.0 && fps.is_finite(), "target fps must be positive");
+        self.min_frame_time = 1.0 / fps;
+    }
+
+    /// Caps the `delta` of a single frame, 0.1s by default. After a stall
+    /// (a debugger pause, a suspended terminal) the game slows down for a
+    /// frame instead of teleporting entities through walls or running a
+    /// burst of catch-up fixed steps. Panics if `max` is not positive.
+    pub fn set_max_delta(&mut self, max: f64) {
+        assert!(max > 0.0, "max delta must be positive");
+        self.max_delta = max;
+    }
+
+    /// Updates entities in fixed steps of `dt` seconds, decoupled from the
+    /// frame rate: each frame runs as many steps as the elapsed time
+    /// covers, carrying the remainder over, then draws once. Every
+    /// `update` then sees the same `delta`, which keeps movement and
+    /// collisions reproducible. A `dt` of 0 goes back to one variable
+    /// step per frame. Panics if `dt` is negative or not finite.
+    pub fn set_fixed_timestep(&mut self, dt: f64) {
+        assert!(dt >= 0.0 && dt.is_finite(), "timestep must be non-negative");
+        self.fixed_timestep = if dt > 0.0 { Some(dt) } else { None };
+        self.accumulated_time = 0.0;
+    }
+
+    /// Freezes the simulation: frames keep polling input and drawing, but
+    /// only entities with a `RunWhilePaused` component update, e.g. a
+    /// pause menu that unpauses on a key. Timers, lifetimes and collisions
+    /// stand still. Resuming starts from a fresh frame, so nothing jumps
+    /// by the time spent paused.
+    pub fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+        self.accumulated_time = 0.0;
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// The key that ends `init` with `ExitReason::Quit`, 'q' by default.
+    /// `None` leaves quitting to the game, through `exit_with`.
+    pub fn set_quit_key(&mut self, key: Option<KeyCode>) {
+        self.quit_key = key;
+    }
+
+    /// Turns on mouse capture for the next `init`. Off by default, since
+    /// capturing the mouse stops the terminal from selecting text.
+    pub fn enable_mouse(&mut self, enabled: bool) {
+        self.ui.mouse_enabled = enabled;
+    }
+
+    /// How many entities exist, including any queued for removal.
+    pub fn entity_count(&self) -> usize {
+        self.entities.len()
+    }
+
+    /// How many components entity `id` holds; 0 for unknown ids.
+    pub fn component_count(&self, id: i64) -> usize {
+        self.components.get(&id).map_or(0, HashMap::len)
+    }
+
+    /// How many components all entities hold together.
+    pub fn total_components(&self) -> usize {
+        self.components.values().map(HashMap::len).sum()
+    }
+
+    /// How many update passes have run, counting the one in progress.
+    /// With a fixed timestep this counts steps rather than drawn frames.
+    pub fn frame_count(&self) -> u64 {
+        self.frame_count
+    }
+
+    /// Simulated seconds since the game started, the sum of every pass's
+    /// `delta`. Time spent paused is not counted.
+    pub fn elapsed(&self) -> f64 {
+        self.elapsed
+    }
+
+    /// Frames per second, averaged over the last 30 frames. 0 before
+    /// the first frame.
+    pub fn fps(&self) -> f64 {
+        let average = self.average_frame_time();
+        if average > 0.0 {
+            1.0 / average
+        } else {
+            0.0
+        }
+    }
+
+    /// The mean `delta` of the last 30 frames, in seconds.
+    pub fn average_frame_time(&self) -> f64 {
+        if self.frame_times.is_empty() {
+            return 0.0;
+        }
+        self.frame_times.iter().sum::<f64>() / self.frame_times.len() as f64
+    }
+
+    /// Draws the FPS, average frame time and entity count over the
+    /// top-right corner of the screen each frame.
+    pub fn show_fps(&mut self, show: bool) {
+        self.fps_overlay = if show { Some(None) } else { None };
+    }
+
+    /// Like `show_fps(true)`, with the overlay's left end at `position` on
+    /// the screen.
+    pub fn show_fps_at(&mut self, position: (u16, u16)) {
+        self.fps_overlay = Some(Some(position));
+    }
+
+    /// The map's `(width, height)` in tiles.
+    pub fn map_size(&self) -> (usize, usize) {
+        (self.map.width(), self.map.height())
+    }
+
+    /// Shorthand for checking `input_map` against this frame's input.
+    pub fn is_action_active(&self, action: &str) -> bool {
+        self.input_map.is_action_active(action, &self.ui)
+    }
+
+    /// Remaps colors as they are drawn, e.g. `Palette::deuteranopia()`,
+    /// without touching game logic. Redraws the whole screen in the new
+    /// colors.
+    pub fn set_palette(&mut self, palette: Palette) {
+        self.ui.palette = palette;
+        self.force_full_redraw();
+    }
+
+    /// Shorthand for `ui.log`; see `UI::set_log_file`.
+    pub fn log(&mut self, message: &str) {
+        let _ = self.ui.log(message);
+    }
+
+    /// Shorthand for `ui.beep`, e.g. on a hit or game over.
+    pub fn beep(&mut self) {
+        let _ = self.ui.beep();
+    }
+
+    /// The map tile under this frame's mouse event, accounting for the
+    /// camera.
+    pub fn mouse_tile(&self) -> Option<(u16, u16)> {
+        self.ui.mouse_cell().map(|x| self.camera.screen_to_map(x))
+    }
+
+    /// Clears the terminal and forgets what was on it, so the next draw
+    /// repaints every cell, e.g. after something else wrote to the screen.
+    /// UI drawing from earlier in the frame is drawn again.
+    pub fn force_full_redraw(&mut self) {
+        let _ = self.ui.clear();
+        // UI cells drawn earlier this frame were queued before the clear.
+        let _ = self.ui.redraw_cells();
+        let (width, height) = self.camera.viewport;
+        self.screen = vec![vec![None; height as usize]; width as usize];
+        self.drawn_camera = None;
+    }
+
     fn draw(&mut self) {
+        // A viewport showing the whole map keeps doing so when the map is
+        // resized, e.g. by `load_level`.
+        let map_size = self.map_size();
+        let (old_width, old_height) =
+            mem::replace(&mut self.drawn_map_size, map_size);
+        if self.camera.viewport == (old_width as u16, old_height as u16) {
+            self.camera.viewport = (map_size.0 as u16, map_size.1 as u16);
+        }
+        self.camera.clamp_to(map_size);
+        let (width, height) = self.camera.viewport;
+        if self.map.resized
+            || self.ui.resized
+            || self.screen.len() != width as usize
+            || self.screen.first().map_or(0, Vec::len) != height as usize
+        {
+            self.force_full_redraw();
+            self.map.resized = false;
+            self.ui.resized = false;
+        }
+        // Cells past the terminal's edge are skipped; writing them would
+        // wrap onto the next line and garble the frame.
+        let (columns, rows) =
+            self.ui.terminal_size().unwrap_or((width, height));
+        let (width, height) = (width.min(columns), height.min(rows));
+        let overlay = self.overlay_cells();
+        let (left, top) = self.view_position();
+        // Cells the UI drew straight on the terminal this frame are left
+        // alone. Where it drew last frame but not this one, or blanked a
+        // row, the terminal no longer shows the map, so those cells are
+        // repainted.
+        let ui_cells: HashSet<(u16, u16)> = mem::take(&mut self.ui.drawn_cells)
+            .into_iter()
+            .flat_map(|(position, character, _)| {
+                let wide = unicode::char_width(character) == 2;
+                let tail = (position.0.saturating_add(1), position.1);
+                [Some(position), wide.then_some(tail)]
+            })
+            .flatten()
+            .collect();
+        let mut stale = mem::take(&mut self.drawn_ui);
+        for row in mem::take(&mut self.ui.cleared_rows) {
+            stale.extend((0..width).map(|x| (x, row)));
+        }
+        for &(x, y) in stale.iter().chain(&ui_cells) {
+            let cell = self
+                .screen
+                .get_mut(x as usize)
+                .and_then(|col| col.get_mut(y as usize));
+            if let Some(cell) = cell {
+                *cell = None;
+            }
+        }
+        // Only the map's dirty tiles and the overlay, now and last frame,
+        // can have changed, unless the screen was reset or the camera
+        // moved. Each dirty tile brings the cell to its right along, in
+        // case a wide character there grew or shrank. Cells are sorted so
+        // columns run left to right.
+        let cells: Vec<(u16, u16)> = if self.drawn_camera == Some((left, top)) {
+            let mut cells: Vec<_> = self
+                .map
+                .dirty
+                .iter()
+                .filter_map(|&(c, r)| {
+                    let x = u16::try_from(i32::from(c) - left).ok()?;
+                    let y = u16::try_from(i32::from(r) - top).ok()?;
+                    Some([(x, y), (x.saturating_add(1), y)])
+                })
+                .flatten()
+                .chain(overlay.keys().copied())
+                .chain(self.drawn_overlay.drain(..))
+                .chain(stale)
+                .filter(|x| x.0 < width && x.1 < height)
+                .filter(|x| !ui_cells.contains(x))
+                .collect();
+            cells.sort_unstable();
+            cells.dedup();
+            cells
+        } else {
+            (0..width)
+                .flat_map(|x| (0..height).map(move |y| (x, y)))
+                .filter(|x| !ui_cells.contains(x))
+                .collect()
+        };
+        self.map.dirty.clear();
+        self.drawn_camera = Some((left, top));
+        self.drawn_overlay = overlay.keys().copied().collect();
+        self.drawn_ui = ui_cells.into_iter().collect();
+        // Only cells that differ from what the terminal already shows are
+        // written, so unchanged cells never flicker. A vacated tile reads
+        // as a blank here, which differs from the character still on the
+        // terminal, so it is blanked exactly once and leaves no ghost.
+        //
+        // A wide character covers the cell to its right too, which is then
+        // recorded as `WIDE_TAIL` and not drawn.
         let map = &self.map;
-        for c in 0..map.width {
-            for r in 0..map.height {
-                if !map.tiles[c][r].current_contents.is_empty()
-                    || !map.tiles[c][r].previous_contents.is_empty()
-                {
-                    let _ = self.ui.terminal_draw(
-                        (c as u16, r as u16),
-                        map.tiles[c][r].display_character,
-                        map.tiles[c][r].color,
-                    );
+        for (x, y) in cells {
+            // Off the map, where a shake can carry the view, is blank.
+            let c = usize::try_from(left + i32::from(x)).ok();
+            let r = usize::try_from(top + i32::from(y)).ok();
+            let tile = c
+                .zip(r)
+                .and_then(|(c, r)| map.tiles.get(c).and_then(|col| col.get(r)));
+            let mut cell = match (overlay.get(&(x, y)), tile) {
+                (Some(&cell), _) => cell,
+                (None, Some(_)) if self.is_faded((x, y)) => {
+                    (' ', Style::default())
                 }
+                (None, Some(tile)) => (tile.display_character, tile.style),
+                (None, None) => (' ', Style::default()),
+            };
+            let covered = x > 0
+                && self.screen[x as usize - 1][y as usize]
+                    .is_some_and(|(c, _)| unicode::char_width(c) == 2);
+            match unicode::char_width(cell.0) {
+                _ if covered => cell = (WIDE_TAIL, Style::default()),
+                // A combining character would merge into its neighbour,
+                // and a wide one at the edge would wrap.
+                0 => cell.0 = char::REPLACEMENT_CHARACTER,
+                2 if x + 1 == width => cell.0 = char::REPLACEMENT_CHARACTER,
+                _ => {}
+            }
+            let drawn = &mut self.screen[x as usize][y as usize];
+            if *drawn != Some(cell) {
+                if cell.0 != WIDE_TAIL {
+                    let _ = self.ui.draw_cell((x, y), cell.0, cell.1);
+                }
+                *drawn = Some(cell);
             }
         }
     }
 
+    // Screen cells covered by the FPS overlay. They go through the same
+    // diff as the map, so the overlay never flickers.
+    fn overlay_cells(&self) -> HashMap<(u16, u16), (char, Style)> {
+        let Some(position) = self.fps_overlay else {
+            return HashMap::new();
+        };
+        let text = format!(
+            "{:.0} fps {:.1} ms {} entities",
+            self.fps(),
+            self.average_frame_time() * 1000.0,
+            self.entity_count()
+        );
+        let (x, y) = position.unwrap_or_else(|| {
+            let width = self.camera.viewport.0;
+            (width.saturating_sub(text.len() as u16), 0)
+        });
+        let style = Style::new(crossterm::style::Color::White).reversed();
+        text.chars()
+            .enumerate()
+            .map(|(i, c)| ((x.saturating_add(i as u16), y), (c, style)))
+            .collect()
+    }
+
+    /// The whole map's characters as rows (`grid[y][x]`), as `draw` would
+    /// show them, without touching the terminal.
+    pub fn render_to_grid(&self) -> Vec<Vec<char>> {
+        let (width, height) = self.map_size();
+        (0..height)
+            .map(|y| {
+                (0..width)
+                    .map(|x| self.map.tiles[x][y].display_character)
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// `render_to_grid` with rows joined by newlines, for golden-file
+    /// comparisons.
+    pub fn render_to_string(&self) -> String {
+        self.render_to_grid()
+            .into_iter()
+            .map(String::from_iter)
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Entities that were on `position` last frame. Positions outside the
+    /// map hold nothing, so they yield an empty `Vec`.
     pub fn query_map(&mut self, position: (u16, u16)) -> Vec<&mut EntityData> {
-        let mut world_entities = Vec::new();
-        for entity in self.entities.iter_mut() {
-            if self.map.tiles[position.0 as usize][position.1 as usize]
-                .previous_contents
-                .contains(&entity.id)
-            {
-                world_entities.push(entity);
-            }
+        self.try_query_map(position).unwrap_or_default()
+    }
+
+    /// Like `query_map`, but returns `None` for positions outside the map.
+    pub fn try_query_map(
+        &mut self,
+        position: (u16, u16),
+    ) -> Option<Vec<&mut EntityData>> {
+        if !self.map.in_bounds(position) {
+            return None;
         }
-        return world_entities;
+        let ids = self.map.previous_contents_at(position);
+        Some(entities_with_ids(&mut self.entities, ids))
+    }
+
+    /// Entities that have written to `position` so far this frame: those
+    /// already updated this pass, unlike `query_map`'s last frame. Checking
+    /// both catches a target whether it updates before or after the
+    /// caller.
+    pub fn query_map_current(
+        &mut self,
+        position: (u16, u16),
+    ) -> Vec<&mut EntityData> {
+        let ids = self.map.contents_at(position);
+        entities_with_ids(&mut self.entities, ids)
+    }
+
+    /// Ids of the entities `query_map_current` returns.
+    pub fn entities_at_current(&self, position: (u16, u16)) -> &[i64] {
+        self.map.contents_at(position)
     }
 
-    pub fn init(&mut self) -> io::Result<()> {
-        let _ = terminal::enable_raw_mode();
+    /// Ids of the entities that were on `position` last frame, the same
+    /// set `query_map` returns. The map keeps a list of ids per tile, so
+    /// this is a constant-time lookup whatever the entity count, and is
+    /// the cheap way to test for collisions in dense scenes. Empty outside
+    /// the map.
+    pub fn entities_at(&self, position: (u16, u16)) -> &[i64] {
+        self.map.previous_contents_at(position)
+    }
+
+    /// Entities that were anywhere in the `size` rectangle at `top_left`
+    /// last frame, each listed once. The region is clipped to the map, so
+    /// off-map or empty regions yield an empty `Vec`.
+    pub fn query_region(
+        &mut self,
+        top_left: (u16, u16),
+        size: (u16, u16),
+    ) -> Vec<&mut EntityData> {
+        let right = (top_left.0 as usize + size.0 as usize).min(self.map.width);
+        let bottom =
+            (top_left.1 as usize + size.1 as usize).min(self.map.height);
+        let mut ids: HashSet<i64> = HashSet::new();
+        for c in top_left.0 as usize..right {
+            for r in top_left.1 as usize..bottom {
+                ids.extend(self.map.tiles[c][r].previous_contents.iter());
+            }
+        }
+        self.entities
+            .iter_mut()
+            .filter(|x| ids.contains(&x.id))
+            .collect()
+    }
 
-        self.ui
-            .stdout
-            .execute(terminal::Clear(terminal::ClearType::All))?
-            .execute(Hide)?;
-        let _ = self.game_loop();
+    /// Stores the closure that builds the game, e.g. spawning the level's
+    /// entities. `init` runs it on a cleared world, and `restart` runs it
+    /// again. Systems survive `clear`, so add them outside `setup`.
+    pub fn on_setup(&mut self, setup: impl Fn(&mut World) + 'static) {
+        self.setup = Some(Rc::new(setup));
+    }
 
-        self.ui
-            .stdout
-            .execute(terminal::Clear(terminal::ClearType::All))?
-            .execute(MoveTo(0, 0))?
-            .execute(Show)?;
+    /// Clears the world and runs the `on_setup` closure again at the start
+    /// of the next tick, e.g. for "press R to restart". Safe to call from
+    /// `update`, since nothing is torn down mid-pass.
+    pub fn restart(&mut self) {
+        self.restart_pending = true;
+    }
 
-        let _ = terminal::disable_raw_mode();
+    fn run_setup(&mut self) {
+        if let Some(setup) = self.setup.clone() {
+            self.clear(false);
+            setup(self);
+        }
+    }
 
-        Ok(())
+    /// Runs the game until 'q' is pressed or an entity calls `exit_with`,
+    /// and returns why it stopped. F3 toggles `UI::set_debug`. With an
+    /// `on_setup` closure the world is rebuilt first, so calling `init`
+    /// again after a game over starts a fresh game.
+    pub fn init(&mut self) -> io::Result<ExitReason> {
+        self.run_setup();
+        let mouse = self.ui.mouse_enabled;
+        self.ui.renderer.begin(mouse)?;
+        let result = self.game_loop();
+        self.ui.renderer.end(mouse)?;
+        result
     }
 
-    fn game_loop(&mut self) -> io::Result<()> {
+    fn game_loop(&mut self) -> io::Result<ExitReason> {
         let mut now = Instant::now();
         let mut delta: f64;
         loop {
             delta = now.elapsed().as_secs_f64();
-            if delta < MIN_FRAME_TIME {
-                thread::sleep(Duration::from_secs_f64(MIN_FRAME_TIME - delta));
+            if delta < self.min_frame_time {
+                thread::sleep(Duration::from_secs_f64(
+                    self.min_frame_time - delta,
+                ));
                 delta = now.elapsed().as_secs_f64();
             }
             now = Instant::now();
             self.ui.update_input();
-            if self
-                .ui
-                .current_input
-                .is_some_and(|x| x == KeyCode::Char('q'))
-            {
-                break;
+            let inputs = self.ui.drain_inputs();
+            if self.quit_key.is_some_and(|x| inputs.contains(&x)) {
+                return Ok(ExitReason::Quit);
             }
-            self.update_entities(delta);
-            self.ui.current_input = None;
+            if inputs.contains(&KeyCode::F(3)) {
+                let debug = self.ui.is_debug();
+                self.ui.set_debug(!debug)?;
+            }
+            let (delta, inputs) =
+                self.replay_frame(delta.min(self.max_delta), inputs);
+            self.tick_with_input(delta, inputs);
+            self.draw();
+            self.ui.flush()?;
+            if let Some(reason) = self.exit.take() {
+                return Ok(reason);
+            }
+        }
+    }
+
+    /// Ends the game loop after the current frame is drawn, making `init`
+    /// return `reason`.
+    pub fn exit_with(&mut self, reason: ExitReason) {
+        self.exit = Some(reason);
+    }
+
+    /// Removes the entity once `seconds` have passed, via its `Lifetime`.
+    pub fn set_lifetime(&mut self, id: i64, seconds: f64) {
+        self.set_component(id, Lifetime { remaining: seconds });
+    }
+
+    fn update_lifetimes(&mut self, delta: f64) {
+        let mut expired = Vec::new();
+        self.for_each_component_mut(|id, x: &mut Lifetime| {
+            x.remaining -= delta;
+            if x.remaining <= 0.0 {
+                expired.push(id);
+            }
+        });
+        for id in expired {
+            self.remove_entity(id);
+        }
+    }
+
+    /// Makes the entity `Invulnerable` for `seconds`, flashing it while it
+    /// lasts. A grant never shortens invulnerability already running.
+    pub fn grant_invulnerability(&mut self, id: i64, seconds: f64) {
+        let remaining = self
+            .get_component::<Invulnerable>(id)
+            .map_or(seconds, |x| x.remaining.max(seconds));
+        self.set_component(
+            id,
+            Invulnerable {
+                remaining,
+                flashing: true,
+            },
+        );
+    }
+
+    /// Whether the entity holds an `Invulnerable` that hasn't run out.
+    /// Damage code should check this before applying a hit.
+    pub fn is_invulnerable(&self, id: i64) -> bool {
+        self.has_component::<Invulnerable>(id)
+    }
+
+    fn update_invulnerability(&mut self, delta: f64) {
+        let mut flashes = Vec::new();
+        self.for_each_component_mut(|id, x: &mut Invulnerable| {
+            x.remaining -= delta;
+            if x.remaining <= 0.0 {
+                flashes.push((id, None));
+            } else if x.flashing {
+                let phase = (x.remaining / INVULNERABLE_FLASH_TIME) as i64;
+                flashes.push((id, Some(phase % 2 == 0)));
+            }
+        });
+        for (id, visible) in flashes {
+            match visible {
+                Some(visible) => self.set_visible(id, visible),
+                None => {
+                    self.remove_component::<Invulnerable>(id);
+                    self.set_visible(id, true);
+                }
+            }
+        }
+    }
+
+    /// Runs `f` with the entity swapped out of its slot, so the entity can
+    /// borrow the world mutably. Does nothing for unknown ids.
+    pub(crate) fn with_entity_taken(
+        &mut self,
+        id: i64,
+        f: impl FnOnce(&mut dyn Entity, &mut World),
+    ) {
+        let Some(index) = self.entities.iter().position(|x| x.id == id) else {
+            return;
+        };
+        let mut entity =
+            mem::replace(&mut self.entities[index].entity, Box::new(Vacant));
+        f(entity.as_mut(), self);
+        self.entities[index].entity = entity;
+    }
+
+    fn process_removals(&mut self) {
+        // `end` may queue further removals, so keep going until none are
+        // left. An id is only ever found once, so each `end` fires once.
+        while !self.removal_queue.is_empty() {
+            let queue = mem::take(&mut self.removal_queue);
+            let (removed, kept): (Vec<_>, Vec<_>) =
+                mem::take(&mut self.entities)
+                    .into_iter()
+                    .partition(|x| queue.contains(&x.id));
+            self.entities = kept;
+            let ids: Vec<i64> = removed.iter().map(|x| x.id).collect();
+            self.map.erase(&ids);
+            for mut entity_data in removed {
+                entity_data.entity.end(self, entity_data.id);
+                self.components.remove(&entity_data.id);
+                self.map.hidden.remove(&entity_data.id);
+                self.map.z_indices.remove(&entity_data.id);
+                self.remove_tags(entity_data.id);
+            }
+        }
+    }
+
+    /// `tick` with `inputs` as the frame's keys, oldest first.
+    pub fn tick_with_input(&mut self, delta: f64, inputs: Vec<KeyCode>) {
+        self.record_frame(delta, &inputs);
+        self.ui.set_frame_inputs(inputs);
+        self.tick(delta);
+    }
+
+    /// Runs one frame of game logic without touching the terminal: no
+    /// input polling, drawing or sleeping, so it works headless in tests.
+    /// Input placed in `ui.frame_inputs` and `ui.current_input` beforehand
+    /// is seen by this frame's first update pass and then cleared; on a
+    /// frame too short for a fixed-timestep pass it is kept for the next
+    /// one. The map keeps the frame's writes until the next tick, so they
+    /// can be inspected.
+    pub fn tick(&mut self, delta: f64) {
+        if mem::take(&mut self.restart_pending) {
+            self.run_setup();
         }
+        if self.frame_times.len() == FPS_SAMPLES {
+            self.frame_times.pop_front();
+        }
+        self.frame_times.push_back(delta);
+        self.update_fade(delta);
+        if self.advance(delta) {
+            self.clear_frame_inputs();
+        }
+    }
 
-        self.ui
-            .stdout
-            .execute(terminal::Clear(terminal::ClearType::All))?
-            .execute(MoveTo(0, 0))?
-            .execute(Show)?;
-        let _ = terminal::disable_raw_mode();
+    fn clear_frame_inputs(&mut self) {
+        self.ui.frame_inputs.clear();
+        self.ui.current_input = None;
+        self.ui.current_mouse = None;
+    }
 
-        Ok(())
+    /// Runs the simulation forward by a frame's `delta`: one update pass,
+    /// or with a fixed timestep, as many fixed passes as the accumulated
+    /// time allows. This frame's input is only seen by the first pass.
+    /// Returns whether any pass ran.
+    fn advance(&mut self, delta: f64) -> bool {
+        if self.paused {
+            self.update_paused_entities(delta);
+            return true;
+        }
+        let Some(step) = self.fixed_timestep else {
+            self.update_entities(delta);
+            return true;
+        };
+        self.accumulated_time += delta;
+        let mut ran = false;
+        while self.accumulated_time >= step {
+            self.update_entities(step);
+            self.accumulated_time -= step;
+            self.clear_frame_inputs();
+            ran = true;
+        }
+        ran
     }
 
+    // The map is cleared at the start rather than the end of a pass, so it
+    // always holds the latest pass's writes for the next draw, even on
+    // frames where a fixed timestep runs no pass at all.
     fn update_entities(&mut self, delta: f64) {
-        if !self.removal_queue.is_empty() {
-            self.entities
-                .retain(|x| !self.removal_queue.contains(&x.id));
-            self.removal_queue.clear();
+        self.frame_count += 1;
+        self.elapsed += delta;
+        self.map.clear();
+        self.process_removals();
+        if self.order_dirty {
+            // Stable, so equal z-indices keep insertion order.
+            self.entities.sort_by_key(|x| x.z_index);
+            self.order_dirty = false;
+        }
+        // Each entity is swapped out of its slot while it runs so it can
+        // borrow the world; slots never move, so this is linear and the
+        // update order is stable. Entities added during the pass are
+        // appended past `entity_count` and first run next frame.
+        let entity_count = self.entities.len();
+        for i in 0..entity_count {
+            self.update_slot(i, delta);
         }
+        self.run_systems(delta);
+        self.run_scheduled(delta);
+        self.update_shake(delta);
+        self.update_lifetimes(delta);
+        self.update_invulnerability(delta);
+        self.draw_entities();
+        self.detect_collisions();
+        self.apply_damage();
+        // Again now, so an entity killed this pass is already off the map
+        // when it is drawn instead of lingering for a frame.
+        self.process_removals();
+    }
+
+    // While paused only `RunWhilePaused` entities update. The map is not
+    // cleared, so the last frame stays on screen under whatever they draw;
+    // only their own writes from the frame before are taken off, so they
+    // neither pile up in tiles' contents nor leave trails behind.
+    fn update_paused_entities(&mut self, delta: f64) {
+        let ids = self.entities_with_component::<RunWhilePaused>();
+        self.map.erase(&ids);
         let entity_count = self.entities.len();
-        let mut current_entity;
-        for _i in 0..entity_count {
-            current_entity = self.entities.remove(0);
-            if !current_entity.started {
-                current_entity.entity.start(self, current_entity.id);
-                current_entity.started = true;
+        for i in 0..entity_count {
+            let id = self.entities[i].id;
+            if self.has_component::<RunWhilePaused>(id) {
+                self.update_slot(i, delta);
+                let x = &self.entities[i];
+                if x.active && x.started {
+                    x.entity.draw(&mut self.map, id);
+                }
             }
-            current_entity.entity.update(delta, self, current_entity.id);
-            self.entities.push(current_entity);
         }
+    }
 
-        self.draw();
-        _ = self.ui.stdout.flush();
-        self.map.clear();
+    // Entities spawned this frame haven't started yet, so they first draw
+    // after their first update.
+    fn draw_entities(&mut self) {
+        for x in &self.entities {
+            if x.active && x.started {
+                x.entity.draw(&mut self.map, x.id);
+            }
+        }
     }
+
+    fn update_slot(&mut self, i: usize, delta: f64) {
+        if !self.entities[i].active || self.entities[i].frozen {
+            return;
+        }
+        let id = self.entities[i].id;
+        let mut entity =
+            mem::replace(&mut self.entities[i].entity, Box::new(Vacant));
+        if !self.entities[i].started {
+            entity.start(self, id);
+            self.entities[i].started = true;
+        }
+        entity.update(delta, self, id);
+        self.entities[i].entity = entity;
+    }
+
     pub fn get_component<T: 'static>(&mut self, id: i64) -> Option<&mut T> {
         match self.components.get_mut(&id) {
             Some(x) => {
-                let type_string =
-                    format!("{:?}", TypeId::of::<T>()).to_string();
-                let component = x.get_mut(&type_string);
+                let component = x.get_mut(&TypeId::of::<T>());
                 match component {
                     Some(cb) => cb.downcast_mut::<T>(),
                     None => None,
@@ -182,75 +1122,126 @@ impl World {
     }
 
     pub fn set_component<T: 'static>(&mut self, id: i64, component: T) {
-        match self.components.get_mut(&id) {
-            Some(x) => {
-                let type_string =
-                    format!("{:?}", TypeId::of::<T>()).to_string();
-                x.insert(type_string, Box::new(component));
-            }
-            None => {}
+        if let Some(x) = self.components.get_mut(&id) {
+            x.insert(TypeId::of::<T>(), Box::new(component));
         }
     }
-}
 
-pub struct Map {
-    width: usize,
-    height: usize,
-    tiles: Vec<Vec<MapTile>>,
-}
+    pub fn get_component_cloned<T: Clone + 'static>(
+        &self,
+        id: i64,
+    ) -> Option<T> {
+        self.components
+            .get(&id)
+            .and_then(|x| x.get(&TypeId::of::<T>()))
+            .and_then(|cb| cb.downcast_ref::<T>())
+            .cloned()
+    }
 
-impl Map {
-    pub fn new(width: usize, height: usize) -> Self {
-        Map {
-            width,
-            height,
-            tiles: vec![
-                vec![
-                    MapTile {
-                        display_character: '#',
-                        color: Color::Black,
-                        current_contents: Vec::new(),
-                        previous_contents: Vec::new()
-                    };
-                    height
-                ];
-                width
-            ],
-        }
-    }
-
-    pub fn clear(&mut self) {
-        for col in self.tiles.iter_mut() {
-            for tile in col.iter_mut() {
-                tile.display_character = ' ';
-                tile.color = crossterm::style::Color::Black;
-                tile.previous_contents.clear();
-                tile.previous_contents.append(&mut tile.current_contents);
-            }
+    /// Takes the entity's `T` out of the store, hands it to `f` alongside
+    /// the world, then puts it back. This allows mutating a component and
+    /// the world at once. Returns `None` without calling `f` if the entity
+    /// has no `T`.
+    pub fn with_component<T: 'static, R>(
+        &mut self,
+        id: i64,
+        f: impl FnOnce(&mut T, &mut World) -> R,
+    ) -> Option<R> {
+        let mut component = self.remove_component::<T>(id)?;
+        let result = f(component.downcast_mut::<T>()?, self);
+        if let Some(x) = self.components.get_mut(&id) {
+            x.insert(TypeId::of::<T>(), component);
         }
+        Some(result)
+    }
+
+    pub fn has_component<T: 'static>(&self, id: i64) -> bool {
+        self.components
+            .get(&id)
+            .is_some_and(|x| x.contains_key(&TypeId::of::<T>()))
     }
 
-    pub fn write(
+    /// Removes and returns the entity's `T`, which the caller can
+    /// `downcast::<T>()`. Returns `None` if the entity or component is
+    /// missing.
+    pub fn remove_component<T: 'static>(
         &mut self,
-        position: (u16, u16),
-        character: char,
-        color: Color,
         id: i64,
+    ) -> Option<Box<dyn Any>> {
+        self.components
+            .get_mut(&id)
+            .and_then(|x| x.remove(&TypeId::of::<T>()))
+    }
+
+    /// Ids of every entity holding a `T`, in ascending id order.
+    pub fn entities_with_component<T: 'static>(&self) -> Vec<i64> {
+        self.components
+            .iter()
+            .filter(|(_, x)| x.contains_key(&TypeId::of::<T>()))
+            .map(|(id, _)| *id)
+            .collect()
+    }
+
+    /// Calls `f` on every stored `T` with its entity's id, in ascending id
+    /// order.
+    pub fn for_each_component_mut<T: 'static>(
+        &mut self,
+        mut f: impl FnMut(i64, &mut T),
     ) {
-        let mut position = position;
-        position.0 = position.0.clamp(0, self.width as u16 - 1);
-        position.1 = position.1.clamp(0, self.height as u16 - 1);
-        let pos = &mut self.tiles[position.0 as usize][position.1 as usize];
-        pos.display_character = character;
-        pos.color = color;
-        pos.current_contents.push(id);
+        for (id, x) in self.components.iter_mut() {
+            if let Some(component) = x
+                .get_mut(&TypeId::of::<T>())
+                .and_then(|cb| cb.downcast_mut::<T>())
+            {
+                f(*id, component);
+            }
+        }
     }
 }
 
-#[derive(Clone)]
-pub struct MapTile {
-    display_character: char,
-    color: Color,
-    current_contents: Vec<i64>, // by ids
-    previous_contents: Vec<i64>,
+fn entities_with_ids<'a>(
+    entities: &'a mut [EntityData],
+    ids: &[i64],
+) -> Vec<&'a mut EntityData> {
+    // Most tiles are empty, so skip the scan over all entities.
+    if ids.is_empty() {
+        return Vec::new();
+    }
+    entities
+        .iter_mut()
+        .filter(|x| ids.contains(&x.id))
+        .collect()
+}
+
+/// Why `World::init` returned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExitReason {
+    /// The player pressed 'q'.
+    Quit,
+    GameOver,
+    Victory,
+    /// A game-specific code.
+    Custom(i32),
+}
+
+/// Marks an entity that keeps updating while the world is paused; see
+/// `World::set_paused`.
+pub struct RunWhilePaused;
+
+/// Seconds left before the engine removes the entity holding this. It is
+/// counted down after each frame's updates.
+#[derive(Serialize, Deserialize)]
+pub struct Lifetime {
+    pub remaining: f64,
+}
+
+/// Seconds left during which damage should be ignored; see
+/// `World::grant_invulnerability`. While `flashing`, the engine toggles
+/// the entity's visibility every tenth of a second and shows it
+/// again once this runs out, so flashing entities shouldn't also be hidden
+/// with `World::set_visible`.
+#[derive(Serialize, Deserialize)]
+pub struct Invulnerable {
+    pub remaining: f64,
+    pub flashing: bool,
 }
@@ -0,0 +1,647 @@
+use std::{
+    collections::{HashMap, HashSet},
+    error::Error,
+    fmt, mem,
+};
+
+use crossterm::style::Color;
+
+use crate::style::Style;
+
+/// A map access at a position outside the map.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OutOfBounds {
+    pub position: (u16, u16),
+}
+
+impl fmt::Display for OutOfBounds {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "position {:?} is outside the map", self.position)
+    }
+}
+
+impl Error for OutOfBounds {}
+
+/// How positions past the edge of the map are brought back onto it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WrapMode {
+    /// Stop at the nearest edge.
+    #[default]
+    Clamp,
+    /// Reappear on the opposite edge, as in a toroidal world.
+    Wrap,
+}
+
+pub struct Map {
+    pub wrap_mode: WrapMode,
+    pub(crate) width: usize,
+    pub(crate) height: usize,
+    pub(crate) tiles: Vec<Vec<MapTile>>,
+    /// Set by `resize` so the next draw clears the terminal.
+    pub(crate) resized: bool,
+    /// Entities hidden with `World::set_visible`: their writes still
+    /// occupy tiles but change nothing on screen.
+    pub(crate) hidden: HashSet<i64>,
+    /// Entities' z-indices, kept in sync by `World::set_z_index`.
+    pub(crate) z_indices: HashMap<i64, i32>,
+    /// Tiles whose character or style may have changed since the last
+    /// draw, which only looks at these.
+    pub(crate) dirty: HashSet<(u16, u16)>,
+}
+
+impl Map {
+    /// An empty map. Panics if either dimension is zero, since there would
+    /// be no tile for positions to land on.
+    pub fn new(width: usize, height: usize) -> Self {
+        assert!(width > 0 && height > 0, "map needs at least one tile");
+        Map {
+            wrap_mode: WrapMode::Clamp,
+            width,
+            height,
+            tiles: vec![
+                vec![
+                    MapTile {
+                        display_character: ' ',
+                        style: Style::new(Color::Black),
+                        current_contents: Vec::new(),
+                        previous_contents: Vec::new(),
+                        background: (' ', Style::new(Color::Black)),
+                        top: None,
+                        layers: Vec::new(),
+                        solid: false,
+                        was_solid: false,
+                    };
+                    height
+                ];
+                width
+            ],
+            resized: false,
+            hidden: HashSet::new(),
+            z_indices: HashMap::new(),
+            dirty: HashSet::new(),
+        }
+    }
+
+    /// Changes the map's dimensions in place. Tiles inside both the old and
+    /// new bounds keep their contents; new tiles start empty. Entities that
+    /// are now off the map end up on the border, since `write` clamps.
+    /// The next draw clears the whole terminal, so nothing from the old
+    /// size lingers. If the camera's viewport was the size of the map, that
+    /// draw resizes it to match; cells past the terminal's edge are never
+    /// drawn. Panics if either dimension is zero, like `Map::new`.
+    pub fn resize(&mut self, width: usize, height: usize) {
+        assert!(width > 0 && height > 0, "map needs at least one tile");
+        let blank = MapTile {
+            display_character: ' ',
+            style: Style::new(Color::Black),
+            current_contents: Vec::new(),
+            previous_contents: Vec::new(),
+            background: (' ', Style::new(Color::Black)),
+            top: None,
+            layers: Vec::new(),
+            solid: false,
+            was_solid: false,
+        };
+        self.tiles.resize(width, vec![blank.clone(); height]);
+        for col in self.tiles.iter_mut() {
+            col.resize(height, blank.clone());
+        }
+        self.width = width;
+        self.height = height;
+        self.resized = true;
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn in_bounds(&self, position: (u16, u16)) -> bool {
+        (position.0 as usize) < self.width
+            && (position.1 as usize) < self.height
+    }
+
+    /// Clamps `position` to at least `margin` tiles from every edge, e.g.
+    /// 1 to stay inside a border; 0 clamps to the whole map. A margin too
+    /// wide for the map pins that axis to its middle.
+    pub fn clamp_position(
+        &self,
+        position: (u16, u16),
+        margin: u16,
+    ) -> (u16, u16) {
+        (
+            clamp_axis(position.0, self.width, margin),
+            clamp_axis(position.1, self.height, margin),
+        )
+    }
+
+    fn tile(&self, position: (u16, u16)) -> Option<&MapTile> {
+        self.tiles
+            .get(position.0 as usize)
+            .and_then(|col| col.get(position.1 as usize))
+    }
+
+    /// Ids written to `position` so far this frame. Empty outside the map.
+    pub fn contents_at(&self, position: (u16, u16)) -> &[i64] {
+        match self.tile(position) {
+            Some(tile) => &tile.current_contents,
+            None => &[],
+        }
+    }
+
+    /// Ids that were on `position` last frame. Empty outside the map.
+    pub fn previous_contents_at(&self, position: (u16, u16)) -> &[i64] {
+        match self.tile(position) {
+            Some(tile) => &tile.previous_contents,
+            None => &[],
+        }
+    }
+
+    pub fn is_occupied(&self, position: (u16, u16)) -> bool {
+        !self.contents_at(position).is_empty()
+    }
+
+    /// The in-bounds tiles above, right of, below and left of `position`,
+    /// in that order. Edges and corners have fewer.
+    pub fn neighbors4(&self, position: (u16, u16)) -> Vec<(u16, u16)> {
+        self.offsets(position, &[(0, -1), (1, 0), (0, 1), (-1, 0)])
+    }
+
+    /// Like `neighbors4`, including diagonals, clockwise from the top.
+    pub fn neighbors8(&self, position: (u16, u16)) -> Vec<(u16, u16)> {
+        self.offsets(
+            position,
+            &[
+                (0, -1),
+                (1, -1),
+                (1, 0),
+                (1, 1),
+                (0, 1),
+                (-1, 1),
+                (-1, 0),
+                (-1, -1),
+            ],
+        )
+    }
+
+    /// The `neighbors8` of `position` that have been written this frame.
+    pub fn occupied_neighbors(&self, position: (u16, u16)) -> Vec<(u16, u16)> {
+        self.neighbors8(position)
+            .into_iter()
+            .filter(|&x| self.is_occupied(x))
+            .collect()
+    }
+
+    /// The box-drawing character for a wall at `position` joining up with
+    /// its wall neighbors above, below, left and right, e.g. `┌` with walls
+    /// right and below, or `┼` with walls all around. Tiles off the map
+    /// are never walls, and a wall with no wall neighbors is `■`.
+    pub fn autotile(
+        &self,
+        position: (u16, u16),
+        is_wall: impl Fn((u16, u16)) -> bool,
+    ) -> char {
+        let [up, right, down, left] = [(0, -1), (1, 0), (0, 1), (-1, 0)]
+            .map(|x| self.offsets(position, &[x]).into_iter().any(&is_wall));
+        match (up, down, left, right) {
+            (false, false, false, false) => '■',
+            (_, _, false, false) => '│',
+            (false, false, _, _) => '─',
+            (true, false, true, false) => '┘',
+            (true, false, false, true) => '└',
+            (false, true, true, false) => '┐',
+            (false, true, false, true) => '┌',
+            (true, true, true, false) => '┤',
+            (true, true, false, true) => '├',
+            (true, false, true, true) => '┴',
+            (false, true, true, true) => '┬',
+            (true, true, true, true) => '┼',
+        }
+    }
+
+    fn offsets(
+        &self,
+        position: (u16, u16),
+        offsets: &[(i32, i32)],
+    ) -> Vec<(u16, u16)> {
+        offsets
+            .iter()
+            .map(|d| (position.0 as i32 + d.0, position.1 as i32 + d.1))
+            .filter(|&(x, y)| x >= 0 && y >= 0)
+            .map(|(x, y)| (x as u16, y as u16))
+            .filter(|&x| self.in_bounds(x))
+            .collect()
+    }
+
+    /// Starts a new frame: every tile goes back to its background and
+    /// its contents move to the previous frame's.
+    pub fn clear(&mut self) {
+        for (x, col) in self.tiles.iter_mut().enumerate() {
+            for (y, tile) in col.iter_mut().enumerate() {
+                if (tile.display_character, tile.style) != tile.background {
+                    self.dirty.insert((x as u16, y as u16));
+                }
+                (tile.display_character, tile.style) = tile.background;
+                tile.top = None;
+                tile.layers.clear();
+                tile.was_solid = mem::take(&mut tile.solid);
+                tile.previous_contents.clear();
+                tile.previous_contents.append(&mut tile.current_contents);
+            }
+        }
+    }
+
+    /// Takes this frame's writes by `ids` off the map. A tile one of them
+    /// was showing goes back to the write that is now on top, as if the
+    /// erased writes never happened, or to its background if none is left.
+    pub(crate) fn erase(&mut self, ids: &[i64]) {
+        for (x, col) in self.tiles.iter_mut().enumerate() {
+            for (y, tile) in col.iter_mut().enumerate() {
+                if tile.current_contents.is_empty() {
+                    continue;
+                }
+                tile.current_contents.retain(|x| !ids.contains(x));
+                tile.layers.retain(|((_, id), _)| !ids.contains(id));
+                if tile.top.is_some_and(|(_, id)| ids.contains(&id)) {
+                    // The last of equal writes wins, as in `place`.
+                    let top = tile.layers.iter().max_by_key(|x| x.0);
+                    tile.top = top.map(|x| x.0);
+                    (tile.display_character, tile.style) =
+                        top.map_or(tile.background, |x| x.1);
+                    self.dirty.insert((x as u16, y as u16));
+                }
+            }
+        }
+    }
+
+    /// Sets what a tile shows when nothing is written to it. Unlike
+    /// `write`, this persists across frames until changed, so static
+    /// scenery can be drawn once instead of every frame. Backgrounds
+    /// belong to no entity: they don't occupy tiles or collide.
+    pub fn set_background(
+        &mut self,
+        position: (u16, u16),
+        character: char,
+        color: Color,
+    ) {
+        self.set_background_styled(position, character, Style::new(color));
+    }
+
+    /// Like `set_background`, with a whole style.
+    pub fn set_background_styled(
+        &mut self,
+        position: (u16, u16),
+        character: char,
+        style: Style,
+    ) {
+        if !self.in_bounds(position) {
+            return;
+        }
+        let tile = &mut self.tiles[position.0 as usize][position.1 as usize];
+        if tile.top.is_none() {
+            (tile.display_character, tile.style) = (character, style);
+            self.dirty.insert(position);
+        }
+        tile.background = (character, style);
+    }
+
+    /// Writes to a tile and records `id` as being on it. The character
+    /// shown is the one from the entity with the highest z-index (see
+    /// `World::set_z_index`), ties going to the higher id, so overlapping
+    /// entities always composite the same way whatever order they update
+    /// in.
+    ///
+    /// Every tile is one terminal cell. A wide character (see
+    /// `unicode::char_width`) is drawn over the tile to its right as well,
+    /// hiding it, and zero-width characters such as combining marks are
+    /// drawn as `�`.
+    pub fn write(
+        &mut self,
+        position: (u16, u16),
+        character: char,
+        color: Color,
+        id: i64,
+    ) {
+        let z = self.z_index(id);
+        self.write_z(position, character, color, id, z);
+    }
+
+    /// Like `write`, also marking the tile as an obstacle for this frame,
+    /// e.g. for a wall as opposed to passable decoration.
+    pub fn write_solid(
+        &mut self,
+        position: (u16, u16),
+        character: char,
+        color: Color,
+        id: i64,
+    ) {
+        self.write(position, character, color, id);
+        let position =
+            self.resolve_position((position.0 as i32, position.1 as i32));
+        self.tiles[position.0 as usize][position.1 as usize].solid = true;
+    }
+
+    /// Whether a `write_solid` hit `position` this frame or last frame, so
+    /// the answer doesn't depend on whether the obstacle updated first.
+    /// Off-map positions are not solid.
+    pub fn is_solid(&self, position: (u16, u16)) -> bool {
+        self.tile(position).is_some_and(|x| x.solid || x.was_solid)
+    }
+
+    /// Like `write` with an explicit `z` for this write alone, in place of
+    /// the entity's z-index.
+    pub fn write_z(
+        &mut self,
+        position: (u16, u16),
+        character: char,
+        color: Color,
+        id: i64,
+        z: i32,
+    ) {
+        self.place(position, id, z, |shown| {
+            shown.0 = character;
+            shown.1.fg = color;
+        });
+    }
+
+    /// Like `write`, also setting the tile's background. Plain `write`s
+    /// leave the background alone, so an entity drawn over a colored floor
+    /// keeps the floor color behind it. Backgrounds reset every frame.
+    pub fn write_bg(
+        &mut self,
+        position: (u16, u16),
+        character: char,
+        color: Color,
+        background: Color,
+        id: i64,
+    ) {
+        let z = self.z_index(id);
+        self.place(position, id, z, |shown| {
+            shown.0 = character;
+            shown.1.fg = color;
+            shown.1.bg = background;
+        });
+    }
+
+    /// Like `write`, replacing the tile's whole style: colors and text
+    /// attributes. Styles reset every frame.
+    pub fn write_styled(
+        &mut self,
+        position: (u16, u16),
+        character: char,
+        style: Style,
+        id: i64,
+    ) {
+        let z = self.z_index(id);
+        self.place(position, id, z, |shown| *shown = (character, style));
+    }
+
+    fn z_index(&self, id: i64) -> i32 {
+        self.z_indices.get(&id).copied().unwrap_or(0)
+    }
+
+    // Records `id` on the tile and `paint`s the tile if this write is the
+    // one to show: not hidden, and on top of what the tile shows so far.
+    // An entity's later write beats its own earlier one. The write is also
+    // kept as painted over the background, for `erase` to fall back on.
+    fn place(
+        &mut self,
+        position: (u16, u16),
+        id: i64,
+        z: i32,
+        paint: impl Fn(&mut (char, Style)),
+    ) {
+        let position =
+            self.resolve_position((position.0 as i32, position.1 as i32));
+        let tile = &mut self.tiles[position.0 as usize][position.1 as usize];
+        tile.current_contents.push(id);
+        if self.hidden.contains(&id) {
+            return;
+        }
+        let mut layer = tile.background;
+        paint(&mut layer);
+        tile.layers.push(((z, id), layer));
+        if tile.top.is_some_and(|x| x > (z, id)) {
+            return;
+        }
+        tile.top = Some((z, id));
+        let mut shown = (tile.display_character, tile.style);
+        paint(&mut shown);
+        (tile.display_character, tile.style) = shown;
+        self.dirty.insert(position);
+    }
+
+    /// Writes `text` one character per tile, rightward from `position`.
+    /// Characters past the right edge of the map are dropped.
+    pub fn draw_text(
+        &mut self,
+        position: (u16, u16),
+        text: &str,
+        color: Color,
+        id: i64,
+    ) {
+        for (i, character) in text.chars().enumerate() {
+            let x = position.0 as usize + i;
+            if x >= self.width {
+                break;
+            }
+            let _ =
+                self.try_write((x as u16, position.1), character, color, id);
+        }
+    }
+
+    /// Draws the outline of a `size.0` by `size.1` rectangle. Tiles off the
+    /// map are skipped.
+    pub fn draw_rect(
+        &mut self,
+        top_left: (u16, u16),
+        size: (u16, u16),
+        character: char,
+        color: Color,
+        id: i64,
+    ) {
+        if size.0 == 0 || size.1 == 0 {
+            return;
+        }
+        let right = top_left.0.saturating_add(size.0 - 1);
+        let bottom = top_left.1.saturating_add(size.1 - 1);
+        for x in top_left.0..=right {
+            let _ = self.try_write((x, top_left.1), character, color, id);
+            let _ = self.try_write((x, bottom), character, color, id);
+        }
+        for y in top_left.1..=bottom {
+            let _ = self.try_write((top_left.0, y), character, color, id);
+            let _ = self.try_write((right, y), character, color, id);
+        }
+    }
+
+    /// Like `draw_rect`, but fills the interior too.
+    pub fn fill_rect(
+        &mut self,
+        top_left: (u16, u16),
+        size: (u16, u16),
+        character: char,
+        color: Color,
+        id: i64,
+    ) {
+        // Only the part on the map is walked, however large `size` is.
+        let end = |start: u16, length: u16, limit: usize| {
+            let limit = limit.min(u16::MAX as usize) as u16;
+            start.saturating_add(length).min(limit)
+        };
+        let right = end(top_left.0, size.0, self.width);
+        let bottom = end(top_left.1, size.1, self.height);
+        for x in top_left.0..right {
+            for y in top_left.1..bottom {
+                let _ = self.try_write((x, y), character, color, id);
+            }
+        }
+    }
+
+    /// Draws a straight line between two tiles, both ends included. Tiles
+    /// off the map are skipped.
+    pub fn draw_line(
+        &mut self,
+        from: (u16, u16),
+        to: (u16, u16),
+        character: char,
+        color: Color,
+        id: i64,
+    ) {
+        for position in line(from, to) {
+            let _ = self.try_write(position, character, color, id);
+        }
+    }
+
+    /// True if no tile strictly between `from` and `to` on a Bresenham
+    /// line is blocking; the end tiles themselves are never checked.
+    pub fn line_of_sight(
+        &self,
+        from: (u16, u16),
+        to: (u16, u16),
+        is_blocking: impl Fn((u16, u16)) -> bool,
+    ) -> bool {
+        let cells = line(from, to);
+        cells.len() <= 2
+            || !cells[1..cells.len() - 1].iter().any(|&x| is_blocking(x))
+    }
+
+    /// The tiles a ray from `from` toward `to` passes through, starting at
+    /// `from`. The ray stops early at the first blocking tile after
+    /// `from`, which is included, or at the edge of the map.
+    pub fn raycast(
+        &self,
+        from: (u16, u16),
+        to: (u16, u16),
+        is_blocking: impl Fn((u16, u16)) -> bool,
+    ) -> Vec<(u16, u16)> {
+        let mut traced = Vec::new();
+        for (i, cell) in line(from, to).into_iter().enumerate() {
+            if !self.in_bounds(cell) {
+                break;
+            }
+            traced.push(cell);
+            if i > 0 && is_blocking(cell) {
+                break;
+            }
+        }
+        traced
+    }
+
+    /// Like `write`, but positions outside the map are reported instead of
+    /// being clamped onto the border.
+    pub fn try_write(
+        &mut self,
+        position: (u16, u16),
+        character: char,
+        color: Color,
+        id: i64,
+    ) -> Result<(), OutOfBounds> {
+        if !self.in_bounds(position) {
+            return Err(OutOfBounds { position });
+        }
+        self.write(position, character, color, id);
+        Ok(())
+    }
+
+    /// Moves `position` by a signed `delta`. Leaving the map never
+    /// underflows: the result is clamped or wrapped per `wrap_mode`.
+    pub fn step(&self, position: (u16, u16), delta: (i16, i16)) -> (u16, u16) {
+        self.resolve_position((
+            position.0 as i32 + delta.0 as i32,
+            position.1 as i32 + delta.1 as i32,
+        ))
+    }
+
+    /// Brings any signed position onto the map per `wrap_mode`. Wrapping
+    /// uses the euclidean remainder, so negative positions and positions
+    /// several map-widths away land on the right tile.
+    pub fn resolve_position(&self, position: (i32, i32)) -> (u16, u16) {
+        let (width, height) = (self.width as i32, self.height as i32);
+        match self.wrap_mode {
+            WrapMode::Clamp => (
+                position.0.clamp(0, width - 1) as u16,
+                position.1.clamp(0, height - 1) as u16,
+            ),
+            WrapMode::Wrap => (
+                position.0.rem_euclid(width) as u16,
+                position.1.rem_euclid(height) as u16,
+            ),
+        }
+    }
+}
+
+fn clamp_axis(value: u16, length: usize, margin: u16) -> u16 {
+    let last = length.saturating_sub(1).min(u16::MAX as usize) as u16;
+    let low = margin.min(last / 2);
+    let high = last.saturating_sub(margin).max(low);
+    value.clamp(low, high)
+}
+
+/// The tiles on a Bresenham line from `from` to `to`, both ends included.
+pub(crate) fn line(from: (u16, u16), to: (u16, u16)) -> Vec<(u16, u16)> {
+    let (mut x, mut y) = (from.0 as i32, from.1 as i32);
+    let (x1, y1) = (to.0 as i32, to.1 as i32);
+    let dx = (x1 - x).abs();
+    let dy = -(y1 - y).abs();
+    let sx = if x < x1 { 1 } else { -1 };
+    let sy = if y < y1 { 1 } else { -1 };
+    let mut error = dx + dy;
+    let mut cells = Vec::new();
+    loop {
+        cells.push((x as u16, y as u16));
+        if x == x1 && y == y1 {
+            return cells;
+        }
+        let doubled = 2 * error;
+        if doubled >= dy {
+            error += dy;
+            x += sx;
+        }
+        if doubled <= dx {
+            error += dx;
+            y += sy;
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct MapTile {
+    pub(crate) display_character: char,
+    pub(crate) style: Style,
+    pub(crate) current_contents: Vec<i64>, // by ids
+    pub(crate) previous_contents: Vec<i64>,
+    /// What `clear` resets the tile to; see `Map::set_background`.
+    pub(crate) background: (char, Style),
+    /// The `(z, id)` of the write now shown, if any this frame.
+    pub(crate) top: Option<(i32, i64)>,
+    /// Every visible write this frame, by `(z, id)`, as it would look on
+    /// its own.
+    pub(crate) layers: Vec<((i32, i64), (char, Style))>,
+    /// Whether a `write_solid` hit the tile this frame, and last frame.
+    pub(crate) solid: bool,
+    pub(crate) was_solid: bool,
+}
@@ -0,0 +1,317 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{map::line, vec2::Vec2, World, WrapMode};
+
+/// Accumulates sub-cell movement ("tilt") so entities can move at any
+/// speed on the integer grid: the tilt grows with `speed * delta` each
+/// frame, and every whole cell it crosses becomes a step.
+#[derive(Clone, Copy, Default, Debug)]
+pub struct Mover {
+    pub tilt: Vec2,
+}
+
+impl Mover {
+    pub fn new() -> Self {
+        Mover { tilt: Vec2::ZERO }
+    }
+
+    /// Adds `direction * speed * delta` to the tilt and returns the whole
+    /// cells to move this frame, keeping the fractional remainder.
+    pub fn advance(
+        &mut self,
+        direction: impl Into<Vec2>,
+        speed: f64,
+        delta: f64,
+    ) -> (i16, i16) {
+        self.tilt += direction.into() * (speed * delta);
+        (take_steps(&mut self.tilt.x), take_steps(&mut self.tilt.y))
+    }
+
+    /// Drops any partial movement.
+    pub fn reset(&mut self) {
+        self.tilt = Vec2::ZERO;
+    }
+}
+
+fn take_steps(tilt: &mut f64) -> i16 {
+    let steps = tilt.trunc();
+    *tilt -= steps;
+    steps as i16
+}
+
+/// An entity's tile, for entities that keep their position as a component
+/// rather than in their own struct. Set it with `World::move_to`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Position {
+    pub x: u16,
+    pub y: u16,
+}
+
+impl World {
+    /// Moves the entity's `Position` to `position`, giving it one if it
+    /// has none. Off-map positions are brought back per the entity's
+    /// `EdgeBehavior`; returns where the entity ended up.
+    pub fn move_to(&mut self, id: i64, position: (i32, i32)) -> (u16, u16) {
+        let position = Vec2::new(position.0 as f64, position.1 as f64);
+        let (x, y) = self.resolve_own_edge(id, position).to_tile();
+        self.set_component(id, Position { x, y });
+        (x, y)
+    }
+
+    /// Moves the entity's `Position` by `delta`, e.g. a step returned by
+    /// `Mover::advance`. Returns `None` if it has no `Position`.
+    pub fn move_by(
+        &mut self,
+        id: i64,
+        delta: (i16, i16),
+    ) -> Option<(u16, u16)> {
+        let (x, y) = self.position(id)?;
+        let to = (x as i32 + delta.0 as i32, y as i32 + delta.1 as i32);
+        Some(self.move_to(id, to))
+    }
+
+    pub fn position(&self, id: i64) -> Option<(u16, u16)> {
+        self.get_component_cloned::<Position>(id)
+            .map(|x| (x.x, x.y))
+    }
+}
+
+/// An entity's exact position within and between tiles, for movement
+/// smoother than whole tiles. Its tile is kept in the entity's `Position`.
+/// Set it with `World::set_subtile` and move it with `World::sweep`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct SubTile {
+    pub position: Vec2,
+}
+
+impl World {
+    /// Places the entity at `position`, clamped onto the map, and its
+    /// `Position` on the tile containing it.
+    pub fn set_subtile(&mut self, id: i64, position: Vec2) {
+        let (width, height) = self.map_size();
+        let position = Vec2::new(
+            position.x.clamp(0.0, width.saturating_sub(1) as f64),
+            position.y.clamp(0.0, height.saturating_sub(1) as f64),
+        );
+        self.set_component(id, SubTile { position });
+        let (x, y) = position.to_tile();
+        self.set_component(id, Position { x, y });
+    }
+
+    pub fn subtile(&self, id: i64) -> Option<Vec2> {
+        self.get_component_cloned::<SubTile>(id).map(|x| x.position)
+    }
+
+    /// Moves the entity's `SubTile` by `delta`, checking every tile on the
+    /// way rather than only where it lands, so a fast mover can't skip
+    /// through a thin wall or past another entity. It stops short of the
+    /// first solid tile (see `World::is_solid`), or on the first tile with
+    /// another entity on it this frame or last, and returns the id of the
+    /// entity it ran into, if any. A stopped entity is left on the tile's
+    /// top-left corner. Otherwise a move past the map's edge follows the
+    /// entity's `EdgeBehavior`, with only the path up to the edge checked.
+    /// Does nothing for entities without a `SubTile`.
+    pub fn sweep(&mut self, id: i64, delta: impl Into<Vec2>) -> Option<i64> {
+        let from = self.subtile(id)?;
+        let (width, height) = self.map_size();
+        let to = from + delta.into();
+        let edge = Vec2::new(
+            to.x.clamp(0.0, width.saturating_sub(1) as f64),
+            to.y.clamp(0.0, height.saturating_sub(1) as f64),
+        );
+        let mut previous = from.to_tile();
+        for tile in line(from.to_tile(), edge.to_tile()).into_iter().skip(1) {
+            let struck = self
+                .entities_at_current(tile)
+                .iter()
+                .chain(self.entities_at(tile))
+                .copied()
+                .find(|&x| x != id);
+            if self.solid_except(tile, Some(id)) {
+                self.set_subtile(id, previous.into());
+                return struck;
+            }
+            if struck.is_some() {
+                self.set_subtile(id, tile.into());
+                return struck;
+            }
+            previous = tile;
+        }
+        let to = self.resolve_own_edge(id, to);
+        self.set_subtile(id, to);
+        None
+    }
+}
+
+/// An entity's velocity in tiles per second, for entities that keep it as
+/// a component. Nothing moves the entity by it; it is there to be read
+/// back after `World::move_to`, `World::move_by` or `World::sweep` flip it
+/// when the entity bounces off the map's edge.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Velocity {
+    pub velocity: Vec2,
+}
+
+impl World {
+    pub fn set_velocity(&mut self, id: i64, velocity: impl Into<Vec2>) {
+        let velocity = velocity.into();
+        self.set_component(id, Velocity { velocity });
+    }
+
+    pub fn velocity(&self, id: i64) -> Option<Vec2> {
+        self.get_component_cloned::<Velocity>(id)
+            .map(|x| x.velocity)
+    }
+}
+
+/// What happens to an entity moved past the map's edge by
+/// `World::move_to`, `World::move_by`, `World::sweep` or
+/// `World::resolve_edge`. An entity without one follows the map's
+/// `wrap_mode`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EdgeBehavior {
+    /// Stop at the edge.
+    Clamp,
+    /// Come back in at the opposite edge.
+    Wrap,
+    /// Reflect off the edge, as far back in as it went past. The moves
+    /// above flip the entity's `Velocity`, if it has one.
+    Bounce,
+    /// Remove the entity, which stops at the edge until it is gone.
+    Despawn,
+}
+
+impl World {
+    pub fn set_edge_behavior(&mut self, id: i64, behavior: EdgeBehavior) {
+        self.set_component(id, behavior);
+    }
+
+    pub fn edge_behavior(&self, id: i64) -> EdgeBehavior {
+        self.get_component_cloned(id)
+            .unwrap_or(match self.map.wrap_mode {
+                WrapMode::Clamp => EdgeBehavior::Clamp,
+                WrapMode::Wrap => EdgeBehavior::Wrap,
+            })
+    }
+
+    /// Brings `position`, in tiles, back onto the map per the entity's
+    /// `EdgeBehavior`, for entities that keep their own position. On a
+    /// bounce, the component of `velocity` across the edge that was hit is
+    /// flipped, so the entity heads back in; for `Despawn`, the entity is
+    /// queued for removal.
+    pub fn resolve_edge(
+        &mut self,
+        id: i64,
+        position: Vec2,
+        velocity: &mut Vec2,
+    ) -> Vec2 {
+        let behavior = self.edge_behavior(id);
+        let (width, height) = self.map_size();
+        let (x, crossed_x) = edge_axis(position.x, width, behavior);
+        let (y, crossed_y) = edge_axis(position.y, height, behavior);
+        if behavior == EdgeBehavior::Bounce {
+            if crossed_x {
+                velocity.x = -velocity.x;
+            }
+            if crossed_y {
+                velocity.y = -velocity.y;
+            }
+        }
+        if behavior == EdgeBehavior::Despawn && (crossed_x || crossed_y) {
+            self.remove_entity(id);
+        }
+        Vec2::new(x, y)
+    }
+
+    // `resolve_edge` with the entity's own `Velocity`, for the moves that
+    // have no velocity passed in.
+    fn resolve_own_edge(&mut self, id: i64, position: Vec2) -> Vec2 {
+        let mut velocity = self.velocity(id).unwrap_or_default();
+        let position = self.resolve_edge(id, position, &mut velocity);
+        if self.has_component::<Velocity>(id) {
+            self.set_velocity(id, velocity);
+        }
+        position
+    }
+}
+
+// One coordinate brought into `[0, length - 1]`, and whether it was
+// outside.
+fn edge_axis(value: f64, length: usize, behavior: EdgeBehavior) -> (f64, bool) {
+    let last = length.saturating_sub(1) as f64;
+    if (0.0..=last).contains(&value) {
+        return (value, false);
+    }
+    let value = match behavior {
+        EdgeBehavior::Wrap if length > 0 => value.rem_euclid(length as f64),
+        EdgeBehavior::Bounce if value < 0.0 => -value,
+        EdgeBehavior::Bounce => 2.0 * last - value,
+        _ => value,
+    };
+    (value.clamp(0.0, last), true)
+}
+
+#[cfg(test)]
+mod tests {
+    use crossterm::style::Color;
+
+    use super::EdgeBehavior;
+    use crate::{render::BufferRenderer, vec2::Vec2, Entity, World};
+
+    struct Thing;
+
+    impl Entity for Thing {}
+
+    // Stands still at (4, 2).
+    struct Target;
+
+    impl Entity for Target {
+        fn update(&mut self, _delta: f64, world: &mut World, id: i64) {
+            world.map.write((4, 2), 'T', Color::White, id);
+        }
+    }
+
+    // Crosses the map in one frame, recording what it hits and when.
+    struct Bullet {
+        hits: Vec<(u64, i64)>,
+    }
+
+    impl Entity for Bullet {
+        fn update(&mut self, _delta: f64, world: &mut World, id: i64) {
+            if let Some(struck) = world.sweep(id, (10.0, 0.0)) {
+                self.hits.push((world.frame_count(), struck));
+            }
+        }
+    }
+
+    fn world() -> World {
+        World::new_with_renderer(5, 5, BufferRenderer::new(5, 5))
+    }
+
+    #[test]
+    fn bounce_flips_stored_velocity() {
+        let mut world = world();
+        let id = world.add_entity_with(Thing, (EdgeBehavior::Bounce,));
+        world.set_velocity(id, (-1.0, 1.0));
+        assert_eq!(world.move_to(id, (-1, 2)), (1, 2));
+        assert_eq!(world.velocity(id), Some(Vec2::new(1.0, 1.0)));
+
+        world.set_subtile(id, Vec2::new(3.0, 3.0));
+        world.sweep(id, (0.0, 2.0));
+        assert_eq!(world.subtile(id), Some(Vec2::new(3.0, 3.0)));
+        assert_eq!(world.velocity(id), Some(Vec2::new(1.0, -1.0)));
+    }
+
+    #[test]
+    fn fast_bullet_hits_stationary_target() {
+        let mut world =
+            World::new_with_renderer(12, 5, BufferRenderer::new(12, 5));
+        let target = world.add_entity_with(Target, ());
+        let bullet = world.add_entity_with(Bullet { hits: Vec::new() }, ());
+        world.set_subtile(bullet, Vec2::new(0.0, 2.0));
+        world.tick(0.1);
+        assert_eq!(world.position(bullet), Some((4, 2)));
+        let hits = &world.get_entity_as::<Bullet>(bullet).unwrap().hits;
+        assert_eq!(hits, &[(1, target)]);
+    }
+}
@@ -0,0 +1,57 @@
+use std::collections::HashMap;
+
+use crossterm::style::Color;
+
+use crate::color::rgb;
+
+/// Substitutes colors as they are drawn, e.g. for colorblind players. Only
+/// the exact colors given to `set` are remapped; everything else, including
+/// `Color::Reset`, is drawn as is.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Palette {
+    substitutions: HashMap<Color, Color>,
+}
+
+impl Palette {
+    /// Leaves every color alone. This is the default.
+    pub fn identity() -> Self {
+        Palette::default()
+    }
+
+    /// For red-green colorblindness (deuteranopia and protanopia): the
+    /// greens become blues and the reds become orange and vermillion, from
+    /// the Okabe-Ito palette. Remaps `Red`, `DarkRed`, `Green` and
+    /// `DarkGreen`.
+    pub fn deuteranopia() -> Self {
+        let mut palette = Palette::default();
+        palette
+            .set(Color::Red, rgb(230, 159, 0))
+            .set(Color::DarkRed, rgb(213, 94, 0))
+            .set(Color::Green, rgb(86, 180, 233))
+            .set(Color::DarkGreen, rgb(0, 114, 178));
+        palette
+    }
+
+    /// For blue-yellow colorblindness (tritanopia): the yellows become
+    /// pinks and the blues become teal. Remaps `Yellow`, `DarkYellow`,
+    /// `Blue` and `DarkBlue`.
+    pub fn tritanopia() -> Self {
+        let mut palette = Palette::default();
+        palette
+            .set(Color::Yellow, rgb(204, 121, 167))
+            .set(Color::DarkYellow, rgb(170, 68, 136))
+            .set(Color::Blue, rgb(0, 158, 115))
+            .set(Color::DarkBlue, rgb(0, 110, 80));
+        palette
+    }
+
+    /// Draws `from` as `to` from now on.
+    pub fn set(&mut self, from: Color, to: Color) -> &mut Self {
+        self.substitutions.insert(from, to);
+        self
+    }
+
+    pub fn apply(&self, color: Color) -> Color {
+        self.substitutions.get(&color).copied().unwrap_or(color)
+    }
+}
@@ -0,0 +1,65 @@
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap},
+};
+
+use crate::Map;
+
+/// Finds a shortest 4-connected path from `start` to `goal` with A*,
+/// moving only through tiles where `is_blocked` is false. `start` itself
+/// is never checked. The path includes both ends, so `start == goal`
+/// gives a single tile. Returns `None` if the goal can't be reached or
+/// either end is off the map.
+pub fn find_path(
+    map: &Map,
+    start: (u16, u16),
+    goal: (u16, u16),
+    is_blocked: impl Fn((u16, u16)) -> bool,
+) -> Option<Vec<(u16, u16)>> {
+    if !map.in_bounds(start) || !map.in_bounds(goal) {
+        return None;
+    }
+    let heuristic = |x: (u16, u16)| {
+        x.0.abs_diff(goal.0) as u32 + x.1.abs_diff(goal.1) as u32
+    };
+
+    // Heap entries are (estimated total cost, cost so far, tile), with the
+    // smallest estimate popped first.
+    let mut open = BinaryHeap::new();
+    let mut costs: HashMap<(u16, u16), u32> = HashMap::new();
+    let mut came_from: HashMap<(u16, u16), (u16, u16)> = HashMap::new();
+    open.push(Reverse((heuristic(start), 0, start)));
+    costs.insert(start, 0);
+
+    while let Some(Reverse((_, cost, current))) = open.pop() {
+        if current == goal {
+            let mut path = vec![current];
+            let mut tile = current;
+            while let Some(&previous) = came_from.get(&tile) {
+                path.push(previous);
+                tile = previous;
+            }
+            path.reverse();
+            return Some(path);
+        }
+        if costs.get(&current).is_some_and(|&best| cost > best) {
+            continue;
+        }
+        for next in map.neighbors4(current) {
+            if is_blocked(next) {
+                continue;
+            }
+            let next_cost = cost + 1;
+            if costs.get(&next).is_none_or(|&best| next_cost < best) {
+                costs.insert(next, next_cost);
+                came_from.insert(next, current);
+                open.push(Reverse((
+                    next_cost + heuristic(next),
+                    next_cost,
+                    next,
+                )));
+            }
+        }
+    }
+    None
+}
@@ -0,0 +1,284 @@
+use std::{
+    any::Any,
+    io::{self, Write},
+    thread,
+};
+
+use crossterm::{
+    cursor,
+    event::{DisableMouseCapture, EnableMouseCapture},
+    queue,
+    style::{
+        Attribute, Attributes, Color, Print, ResetColor, SetAttribute,
+        SetAttributes, SetBackgroundColor, SetForegroundColor,
+    },
+    terminal,
+};
+
+use crate::style::Style;
+
+/// Where the engine sends its output. Drawing calls may be buffered until
+/// `flush`, which the game loop calls once per frame.
+pub trait Renderer: Any {
+    fn draw_cell(
+        &mut self,
+        position: (u16, u16),
+        character: char,
+        style: Style,
+    ) -> io::Result<()>;
+
+    /// Blanks the whole screen.
+    fn clear(&mut self) -> io::Result<()>;
+
+    /// Blanks one row of the screen.
+    fn clear_line(&mut self, row: u16) -> io::Result<()>;
+
+    fn flush(&mut self) -> io::Result<()>;
+
+    /// Rings the bell. Does nothing by default.
+    fn beep(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// The screen's size in cells, if it has one.
+    fn size(&self) -> Option<(u16, u16)> {
+        None
+    }
+
+    /// Called by `World::init` before the game loop starts.
+    fn begin(&mut self, _mouse: bool) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// Called by `World::init` after the game loop ends.
+    fn end(&mut self, _mouse: bool) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Draws to the terminal through crossterm, by default on stdout.
+pub struct CrosstermRenderer {
+    stdout: Box<dyn Write>,
+    // Where the terminal cursor and colors are known to be, so
+    // `draw_cell` can skip commands that would change nothing.
+    cursor: Option<(u16, u16)>,
+    pen: Option<Color>,
+    paper: Option<Color>,
+    attributes: Option<Attributes>,
+    // Whether mouse capture is on, set between `begin` and `end`.
+    active: Option<bool>,
+}
+
+impl Default for CrosstermRenderer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CrosstermRenderer {
+    pub fn new() -> Self {
+        CrosstermRenderer::with_output(io::stdout())
+    }
+
+    /// A renderer that writes its escape codes to `output` instead of
+    /// stdout, e.g. a pipe or a buffer. Raw mode is still switched on the
+    /// process's terminal in `begin`, since that is where keys come from.
+    pub fn with_output(output: impl Write + 'static) -> Self {
+        CrosstermRenderer {
+            stdout: Box::new(output),
+            cursor: None,
+            pen: None,
+            paper: None,
+            attributes: None,
+            active: None,
+        }
+    }
+
+    // Puts the terminal back to default colors and forgets the cursor, so
+    // nothing drawn afterward inherits a cell's colors.
+    fn reset_pen(&mut self) -> io::Result<()> {
+        queue!(self.stdout, SetAttribute(Attribute::Reset), ResetColor)?;
+        self.cursor = None;
+        self.pen = None;
+        self.paper = None;
+        self.attributes = None;
+        Ok(())
+    }
+}
+
+impl Renderer for CrosstermRenderer {
+    /// Queues one cell. A run of cells along a row in one style costs a
+    /// single cursor move and color change.
+    fn draw_cell(
+        &mut self,
+        position: (u16, u16),
+        character: char,
+        style: Style,
+    ) -> io::Result<()> {
+        if self.cursor != Some(position) {
+            queue!(self.stdout, cursor::MoveTo(position.0, position.1))?;
+        }
+        let attributes = style.attributes();
+        if self.attributes != Some(attributes) {
+            // Resetting attributes also resets colors, so set both again.
+            queue!(
+                self.stdout,
+                SetAttribute(Attribute::Reset),
+                SetAttributes(attributes)
+            )?;
+            self.attributes = Some(attributes);
+            self.pen = None;
+            self.paper = None;
+        }
+        if self.pen != Some(style.fg) {
+            queue!(self.stdout, SetForegroundColor(style.fg))?;
+            self.pen = Some(style.fg);
+        }
+        if self.paper != Some(style.bg) {
+            queue!(self.stdout, SetBackgroundColor(style.bg))?;
+            self.paper = Some(style.bg);
+        }
+        queue!(self.stdout, Print(character))?;
+        let width = crate::unicode::char_width(character);
+        self.cursor = Some((position.0.saturating_add(width), position.1));
+        Ok(())
+    }
+
+    fn clear(&mut self) -> io::Result<()> {
+        self.reset_pen()?;
+        queue!(self.stdout, terminal::Clear(terminal::ClearType::All))
+    }
+
+    fn clear_line(&mut self, row: u16) -> io::Result<()> {
+        self.reset_pen()?;
+        queue!(
+            self.stdout,
+            cursor::MoveTo(0, row),
+            terminal::Clear(terminal::ClearType::CurrentLine)
+        )
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.stdout.flush()
+    }
+
+    fn size(&self) -> Option<(u16, u16)> {
+        terminal::size().ok()
+    }
+
+    fn beep(&mut self) -> io::Result<()> {
+        queue!(self.stdout, Print('\x07'))
+    }
+
+    /// Switches the terminal to raw mode and hides the cursor.
+    fn begin(&mut self, mouse: bool) -> io::Result<()> {
+        terminal::enable_raw_mode()?;
+        self.active = Some(mouse);
+        self.clear()?;
+        queue!(self.stdout, cursor::Hide)?;
+        if mouse {
+            queue!(self.stdout, EnableMouseCapture)?;
+        }
+        self.flush()
+    }
+
+    /// Restores the terminal to how `begin` found it.
+    fn end(&mut self, _mouse: bool) -> io::Result<()> {
+        self.restore()
+    }
+}
+
+impl CrosstermRenderer {
+    fn restore(&mut self) -> io::Result<()> {
+        let Some(mouse) = self.active.take() else {
+            return Ok(());
+        };
+        if mouse {
+            queue!(self.stdout, DisableMouseCapture)?;
+        }
+        self.reset_pen()?;
+        // Keep a panic message on screen rather than clearing it away.
+        if !thread::panicking() {
+            queue!(
+                self.stdout,
+                terminal::Clear(terminal::ClearType::All),
+                cursor::MoveTo(0, 0)
+            )?;
+        }
+        queue!(self.stdout, cursor::Show)?;
+        self.flush()?;
+        terminal::disable_raw_mode()
+    }
+}
+
+/// Undoes `begin` if `end` never ran, so the terminal is usable again even
+/// if the game panics or returns early.
+impl Drop for CrosstermRenderer {
+    fn drop(&mut self) {
+        let _ = self.restore();
+    }
+}
+
+/// Records drawn characters in memory instead of showing them, for
+/// headless runs and snapshot tests. Styles are not recorded.
+#[derive(Default)]
+pub struct BufferRenderer {
+    /// The recorded screen, indexed `[row][column]`. Grows as cells are
+    /// drawn past its edges.
+    pub cells: Vec<Vec<char>>,
+}
+
+impl BufferRenderer {
+    pub fn new(width: u16, height: u16) -> Self {
+        BufferRenderer {
+            cells: vec![vec![' '; width as usize]; height as usize],
+        }
+    }
+
+    /// The recorded screen as newline-separated rows.
+    pub fn text(&self) -> String {
+        self.cells
+            .iter()
+            .map(|row| row.iter().collect::<String>())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+impl Renderer for BufferRenderer {
+    fn draw_cell(
+        &mut self,
+        position: (u16, u16),
+        character: char,
+        _style: Style,
+    ) -> io::Result<()> {
+        let (x, y) = (position.0 as usize, position.1 as usize);
+        if self.cells.len() <= y {
+            self.cells.resize(y + 1, Vec::new());
+        }
+        let row = &mut self.cells[y];
+        if row.len() <= x {
+            row.resize(x + 1, ' ');
+        }
+        row[x] = character;
+        Ok(())
+    }
+
+    fn clear(&mut self) -> io::Result<()> {
+        for row in self.cells.iter_mut() {
+            row.fill(' ');
+        }
+        Ok(())
+    }
+
+    fn clear_line(&mut self, row: u16) -> io::Result<()> {
+        if let Some(row) = self.cells.get_mut(row as usize) {
+            row.fill(' ');
+        }
+        Ok(())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
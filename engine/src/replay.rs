@@ -0,0 +1,84 @@
+use std::io::{Read, Write};
+
+use crossterm::event::KeyCode;
+use serde::{Deserialize, Serialize};
+
+use crate::World;
+
+/// One frame of a `Recording`: the `delta` it ran with and the keys it
+/// received, oldest first.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecordedFrame {
+    pub delta: f64,
+    pub keys: Vec<KeyCode>,
+}
+
+/// The input of a run, frame by frame. Replayed into a world that starts
+/// from the same state with the same RNG seed, it reproduces the run.
+/// Mouse events are not recorded.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Recording {
+    pub frames: Vec<RecordedFrame>,
+}
+
+impl Recording {
+    /// Writes the recording as JSON.
+    pub fn save(&self, writer: impl Write) -> serde_json::Result<()> {
+        serde_json::to_writer(writer, self)
+    }
+
+    pub fn load(reader: impl Read) -> serde_json::Result<Self> {
+        serde_json::from_reader(reader)
+    }
+}
+
+impl World {
+    /// Starts logging the delta and keys of every frame run by the game
+    /// loop or `tick_with_input`, discarding any recording in progress.
+    pub fn start_recording(&mut self) {
+        self.recording = Some(Recording::default());
+    }
+
+    /// Stops logging and returns what was recorded, or `None` if nothing
+    /// was being recorded.
+    pub fn stop_recording(&mut self) -> Option<Recording> {
+        self.recording.take()
+    }
+
+    /// Makes the game loop replay `recording` in place of the keyboard,
+    /// one recorded frame per frame. Its deltas replace the measured ones.
+    /// Live input resumes when it runs out; 'q' still quits meanwhile.
+    pub fn play_recording(&mut self, recording: Recording) {
+        self.playback = recording.frames.into();
+    }
+
+    /// Runs every frame of `recording` straight away with `tick`, without
+    /// a terminal, e.g. to replay a bug report in a test.
+    pub fn run_recording(&mut self, recording: &Recording) {
+        for frame in &recording.frames {
+            self.tick_with_input(frame.delta, frame.keys.clone());
+        }
+    }
+
+    /// The next recorded frame while a playback is running, otherwise the
+    /// live `delta` and `keys`.
+    pub(crate) fn replay_frame(
+        &mut self,
+        delta: f64,
+        keys: Vec<KeyCode>,
+    ) -> (f64, Vec<KeyCode>) {
+        match self.playback.pop_front() {
+            Some(frame) => (frame.delta, frame.keys),
+            None => (delta, keys),
+        }
+    }
+
+    pub(crate) fn record_frame(&mut self, delta: f64, keys: &[KeyCode]) {
+        if let Some(recording) = &mut self.recording {
+            recording.frames.push(RecordedFrame {
+                delta,
+                keys: keys.to_vec(),
+            });
+        }
+    }
+}
@@ -0,0 +1,79 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::World;
+
+/// A small seedable xorshift64* generator. Not for cryptography, but the
+/// same seed always gives the same sequence, which keeps runs replayable.
+#[derive(Debug, Clone)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Default for Rng {
+    /// Seeded from the system clock.
+    fn default() -> Self {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |x| x.as_nanos() as u64);
+        Rng::new(nanos)
+    }
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        // Scrambled with splitmix64 so that nearby seeds diverge at once,
+        // and nudged off zero, which xorshift can never leave.
+        let mut z = seed.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^= z >> 31;
+        Rng { state: z.max(1) }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    /// A number in `[0, 1)`.
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// A number in `[min, max)`. Panics if the range is empty.
+    pub fn range(&mut self, min: i64, max: i64) -> i64 {
+        assert!(min < max, "empty range");
+        let span = max.wrapping_sub(min) as u64;
+        min.wrapping_add((self.next_u64() % span) as i64)
+    }
+
+    /// True with probability `p`.
+    pub fn chance(&mut self, p: f64) -> bool {
+        self.next_f64() < p
+    }
+}
+
+impl World {
+    /// Restarts the world's random sequence from `seed`, making gameplay
+    /// reproducible. Otherwise it is seeded from the clock.
+    pub fn seed_rng(&mut self, seed: u64) {
+        self.rng = Rng::new(seed);
+    }
+
+    /// A random number in `[min, max)`. Panics if the range is empty.
+    pub fn rng_range(&mut self, min: i64, max: i64) -> i64 {
+        self.rng.range(min, max)
+    }
+
+    /// True with probability `p`.
+    pub fn rng_bool(&mut self, p: f64) -> bool {
+        self.rng.chance(p)
+    }
+
+    /// The world's generator, for anything the shorthands don't cover.
+    pub fn rng(&mut self) -> &mut Rng {
+        &mut self.rng
+    }
+}
@@ -0,0 +1,287 @@
+use std::{
+    any::{Any, TypeId},
+    collections::{BTreeMap, HashMap},
+    error::Error,
+    fmt,
+    io::{self, Read, Write},
+};
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    movement::{EdgeBehavior, Position, SubTile, Velocity},
+    CollisionLayer, Entity, EntityData, Facing, Health, Invulnerable, Lifetime,
+    Map, Solid, World,
+};
+
+type SaveFn = Box<dyn Fn(&dyn Any) -> serde_json::Result<Value>>;
+type LoadEntityFn = Box<dyn Fn(Value) -> serde_json::Result<Box<dyn Entity>>>;
+type LoadComponentFn = Box<dyn Fn(Value) -> serde_json::Result<Box<dyn Any>>>;
+
+/// The entity and component types `World::save` and `World::load` know
+/// how to (de)serialize, each under a name that is written to the save.
+/// Names must stay the same between saving and loading. The engine's own
+/// `Lifetime`, `Invulnerable`, `CollisionLayer`, `Solid`, `Facing`,
+/// `Position`, `SubTile`, `Health`, `EdgeBehavior` and `Velocity`
+/// components come registered.
+pub struct SaveRegistry {
+    savers: HashMap<TypeId, (String, SaveFn)>,
+    entity_loaders: HashMap<String, LoadEntityFn>,
+    component_loaders: HashMap<String, (TypeId, LoadComponentFn)>,
+}
+
+impl Default for SaveRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SaveRegistry {
+    pub fn new() -> Self {
+        let mut registry = SaveRegistry {
+            savers: HashMap::new(),
+            entity_loaders: HashMap::new(),
+            component_loaders: HashMap::new(),
+        };
+        registry
+            .register_component::<Lifetime>("Lifetime")
+            .register_component::<Invulnerable>("Invulnerable")
+            .register_component::<CollisionLayer>("CollisionLayer")
+            .register_component::<Solid>("Solid")
+            .register_component::<Facing>("Facing")
+            .register_component::<Position>("Position")
+            .register_component::<SubTile>("SubTile")
+            .register_component::<Health>("Health")
+            .register_component::<EdgeBehavior>("EdgeBehavior")
+            .register_component::<Velocity>("Velocity");
+        registry
+    }
+
+    pub fn register_entity<T>(&mut self, name: &str) -> &mut Self
+    where
+        T: Entity + Serialize + DeserializeOwned,
+    {
+        self.savers
+            .insert(TypeId::of::<T>(), (name.to_string(), saver::<T>()));
+        self.entity_loaders.insert(
+            name.to_string(),
+            Box::new(|value| {
+                Ok(Box::new(serde_json::from_value::<T>(value)?) as Box<_>)
+            }),
+        );
+        self
+    }
+
+    pub fn register_component<T>(&mut self, name: &str) -> &mut Self
+    where
+        T: Serialize + DeserializeOwned + 'static,
+    {
+        self.savers
+            .insert(TypeId::of::<T>(), (name.to_string(), saver::<T>()));
+        self.component_loaders.insert(
+            name.to_string(),
+            (
+                TypeId::of::<T>(),
+                Box::new(|value| {
+                    Ok(Box::new(serde_json::from_value::<T>(value)?) as Box<_>)
+                }),
+            ),
+        );
+        self
+    }
+
+    fn save(
+        &self,
+        value: &dyn Any,
+    ) -> Option<(&str, serde_json::Result<Value>)> {
+        let (name, save) = self.savers.get(&value.type_id())?;
+        Some((name, save(value)))
+    }
+}
+
+fn saver<T: Serialize + 'static>() -> SaveFn {
+    Box::new(|value| {
+        serde_json::to_value(
+            value.downcast_ref::<T>().expect("registered type"),
+        )
+    })
+}
+
+#[derive(Debug)]
+pub enum SaveError {
+    Io(io::Error),
+    Json(serde_json::Error),
+    /// A save names a type missing from the registry.
+    UnknownType(String),
+}
+
+impl fmt::Display for SaveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SaveError::Io(error) => write!(f, "could not access save: {error}"),
+            SaveError::Json(error) => write!(f, "malformed save: {error}"),
+            SaveError::UnknownType(name) => {
+                write!(f, "save contains unregistered type {name:?}")
+            }
+        }
+    }
+}
+
+impl Error for SaveError {}
+
+impl From<io::Error> for SaveError {
+    fn from(error: io::Error) -> Self {
+        SaveError::Io(error)
+    }
+}
+
+impl From<serde_json::Error> for SaveError {
+    fn from(error: serde_json::Error) -> Self {
+        SaveError::Json(error)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct Snapshot {
+    width: usize,
+    height: usize,
+    next_id: i64,
+    entities: Vec<EntitySnapshot>,
+    tags: HashMap<String, Vec<i64>>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct EntitySnapshot {
+    id: i64,
+    kind: String,
+    data: Value,
+    started: bool,
+    z_index: i32,
+    active: bool,
+    #[serde(default)]
+    frozen: bool,
+    visible: bool,
+    components: BTreeMap<String, Value>,
+}
+
+impl World {
+    /// Writes the map size, every entity of a registered type with its
+    /// registered components, and tags as JSON. Unregistered entities and
+    /// components are left out, so a partial registry gives a partial
+    /// save. Tile contents are not saved; entities redraw them on the next
+    /// update.
+    pub fn save(
+        &self,
+        writer: impl Write,
+        registry: &SaveRegistry,
+    ) -> Result<(), SaveError> {
+        let mut entities = Vec::new();
+        for x in &self.entities {
+            let Some((kind, data)) = registry.save(x.entity.as_ref()) else {
+                continue;
+            };
+            let mut components = BTreeMap::new();
+            let stored = self.components.get(&x.id).into_iter().flatten();
+            for (_, component) in stored {
+                if let Some((name, value)) = registry.save(component.as_ref()) {
+                    components.insert(name.to_string(), value?);
+                }
+            }
+            entities.push(EntitySnapshot {
+                id: x.id,
+                kind: kind.to_string(),
+                data: data?,
+                started: x.started,
+                z_index: x.z_index,
+                active: x.active,
+                frozen: x.frozen,
+                visible: !self.map.hidden.contains(&x.id),
+                components,
+            });
+        }
+        let saved: Vec<i64> = entities.iter().map(|x| x.id).collect();
+        let tags = self
+            .tags
+            .iter()
+            .map(|(tag, ids)| {
+                let ids = ids.iter().filter(|x| saved.contains(x)).copied();
+                (tag.clone(), ids.collect())
+            })
+            .collect();
+        let snapshot = Snapshot {
+            width: self.map.width(),
+            height: self.map.height(),
+            next_id: self.next_id,
+            entities,
+            tags,
+        };
+        serde_json::to_writer(writer, &snapshot)?;
+        Ok(())
+    }
+
+    /// Replaces the world's entities with those in a save written by
+    /// `save`, keeping ids. Entities that already ran `start` don't run it
+    /// again. The world is left untouched if the save can't be read.
+    pub fn load(
+        &mut self,
+        reader: impl Read,
+        registry: &SaveRegistry,
+    ) -> Result<(), SaveError> {
+        let snapshot: Snapshot = serde_json::from_reader(reader)?;
+        if snapshot.width == 0 || snapshot.height == 0 {
+            let error = serde::de::Error::custom("map has no tiles");
+            return Err(SaveError::Json(error));
+        }
+        let mut entities = Vec::new();
+        let mut components = BTreeMap::new();
+        for x in snapshot.entities {
+            let load = registry
+                .entity_loaders
+                .get(&x.kind)
+                .ok_or_else(|| SaveError::UnknownType(x.kind.clone()))?;
+            let mut loaded = HashMap::new();
+            for (name, value) in x.components {
+                let (type_id, load) = registry
+                    .component_loaders
+                    .get(&name)
+                    .ok_or(SaveError::UnknownType(name))?;
+                loaded.insert(*type_id, load(value)?);
+            }
+            components.insert(x.id, loaded);
+            entities.push((
+                EntityData {
+                    entity: load(x.data)?,
+                    id: x.id,
+                    started: x.started,
+                    z_index: x.z_index,
+                    active: x.active,
+                    frozen: x.frozen,
+                },
+                x.visible,
+            ));
+        }
+
+        self.clear(false);
+        let wrap_mode = self.map.wrap_mode;
+        self.map = Map::new(snapshot.width, snapshot.height);
+        self.map.wrap_mode = wrap_mode;
+        self.map.resized = true;
+        for (entity_data, visible) in entities {
+            if !visible {
+                self.map.hidden.insert(entity_data.id);
+            }
+            if entity_data.z_index != 0 {
+                self.map
+                    .z_indices
+                    .insert(entity_data.id, entity_data.z_index);
+            }
+            self.entities.push(entity_data);
+        }
+        self.components = components;
+        self.tags = snapshot.tags;
+        self.next_id = snapshot.next_id;
+        self.order_dirty = true;
+        Ok(())
+    }
+}
@@ -0,0 +1,116 @@
+use crate::World;
+
+/// Identifies a task passed to `World::schedule` or
+/// `World::schedule_interval`, for `World::cancel`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TaskHandle(u64);
+
+pub(crate) struct ScheduledTask {
+    handle: TaskHandle,
+    remaining: f64,
+    // `None` for a one-shot task.
+    period: Option<f64>,
+    callback: Box<dyn FnMut(&mut World)>,
+}
+
+impl World {
+    /// Runs `f` once, after `delay` seconds of game time.
+    pub fn schedule(
+        &mut self,
+        delay: f64,
+        f: impl FnOnce(&mut World) + 'static,
+    ) -> TaskHandle {
+        let mut f = Some(f);
+        self.add_task(delay, None, move |world| {
+            if let Some(f) = f.take() {
+                f(world);
+            }
+        })
+    }
+
+    /// Runs `f` every `period` seconds of game time until cancelled. After
+    /// a long frame it runs once for each period that frame covered.
+    /// Panics if `period` is not positive.
+    pub fn schedule_interval(
+        &mut self,
+        period: f64,
+        f: impl FnMut(&mut World) + 'static,
+    ) -> TaskHandle {
+        assert!(period > 0.0, "interval period must be positive");
+        self.add_task(period, Some(period), f)
+    }
+
+    /// Stops a scheduled task from running again. Cancelling a task that
+    /// already finished does nothing.
+    pub fn cancel(&mut self, handle: TaskHandle) {
+        self.tasks.retain(|x| x.handle != handle);
+        self.cancelled_tasks.push(handle);
+    }
+
+    fn add_task(
+        &mut self,
+        delay: f64,
+        period: Option<f64>,
+        f: impl FnMut(&mut World) + 'static,
+    ) -> TaskHandle {
+        let handle = TaskHandle(self.next_task_id);
+        self.next_task_id += 1;
+        self.tasks.push(ScheduledTask {
+            handle,
+            remaining: delay,
+            period,
+            callback: Box::new(f),
+        });
+        handle
+    }
+
+    /// Counts every task down by `delta` and runs the ones that are due, in
+    /// the order they were scheduled. Tasks scheduled from a callback
+    /// first count down next pass.
+    pub(crate) fn run_scheduled(&mut self, delta: f64) {
+        let mut tasks = std::mem::take(&mut self.tasks);
+        self.cancelled_tasks.clear();
+        tasks.retain_mut(|task| {
+            task.remaining -= delta;
+            while task.remaining <= 0.0 {
+                if self.cancelled_tasks.contains(&task.handle) {
+                    return false;
+                }
+                (task.callback)(self);
+                match task.period {
+                    Some(period) => task.remaining += period,
+                    None => return false,
+                }
+            }
+            !self.cancelled_tasks.contains(&task.handle)
+        });
+        // A callback may have cancelled a task that already ran this pass.
+        tasks.retain(|x| !self.cancelled_tasks.contains(&x.handle));
+        tasks.append(&mut self.tasks);
+        self.tasks = tasks;
+        self.cancelled_tasks.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::Cell, rc::Rc};
+
+    use crate::{render::BufferRenderer, World};
+
+    #[test]
+    fn task_cancelled_by_a_later_task_stops() {
+        let mut world =
+            World::new_with_renderer(1, 1, BufferRenderer::new(1, 1));
+        let runs = Rc::new(Cell::new(0));
+        let counter = runs.clone();
+        let a = world.schedule_interval(1.0, move |_| {
+            counter.set(counter.get() + 1);
+        });
+        world.schedule(1.5, move |world| world.cancel(a));
+        for _ in 0..6 {
+            world.tick(0.5);
+        }
+        assert_eq!(runs.get(), 1);
+    }
+}
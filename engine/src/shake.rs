@@ -0,0 +1,53 @@
+use crate::World;
+
+pub(crate) struct Shake {
+    intensity: f64,
+    duration: f64,
+    remaining: f64,
+}
+
+impl World {
+    /// Shakes the screen for `duration` seconds: each frame the view is
+    /// drawn up to `intensity` tiles off the camera's position in a random
+    /// direction, decaying linearly to nothing. The camera itself never
+    /// moves. The view may be shaken past the map's edge, which shows as
+    /// blank cells, so a map no bigger than the viewport still shakes.
+    /// Offsets come from
+    /// the world's generator during the update, so they replay exactly
+    /// under a fixed seed. A new shake replaces one in progress.
+    pub fn shake(&mut self, intensity: f64, duration: f64) {
+        self.shake = (intensity > 0.0 && duration > 0.0).then_some(Shake {
+            intensity,
+            duration,
+            remaining: duration,
+        });
+        self.shake_offset = (0, 0);
+    }
+
+    pub(crate) fn update_shake(&mut self, delta: f64) {
+        let Some(shake) = &mut self.shake else {
+            return;
+        };
+        shake.remaining -= delta;
+        if shake.remaining <= 0.0 {
+            self.shake = None;
+            self.shake_offset = (0, 0);
+            return;
+        }
+        let magnitude = shake.intensity * shake.remaining / shake.duration;
+        let x = (self.rng.next_f64() * 2.0 - 1.0) * magnitude;
+        let y = (self.rng.next_f64() * 2.0 - 1.0) * magnitude;
+        self.shake_offset = (x.round() as i16, y.round() as i16);
+    }
+
+    // Where the view is drawn from this frame: the camera's position, kept
+    // on the map, then off by the shake, which may leave it.
+    pub(crate) fn view_position(&self) -> (i32, i32) {
+        let mut camera = self.camera;
+        camera.clamp_to(self.map_size());
+        (
+            i32::from(camera.position.0) + i32::from(self.shake_offset.0),
+            i32::from(camera.position.1) + i32::from(self.shake_offset.1),
+        )
+    }
+}
@@ -0,0 +1,63 @@
+use crossterm::style::Color;
+
+use crate::Map;
+
+/// A picture several tiles wide and tall. Cells are indexed
+/// `[row][column]`; `None` cells are transparent.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sprite {
+    pub cells: Vec<Vec<Option<(char, Color)>>>,
+}
+
+impl Sprite {
+    pub fn new(cells: Vec<Vec<Option<(char, Color)>>>) -> Self {
+        Sprite { cells }
+    }
+
+    /// A sprite drawn as ASCII art in one color, one row per line. Spaces
+    /// are transparent.
+    pub fn from_text(art: &str, color: Color) -> Self {
+        let cells = art
+            .lines()
+            .map(|line| {
+                line.chars()
+                    .map(|c| (c != ' ').then_some((c, color)))
+                    .collect()
+            })
+            .collect();
+        Sprite { cells }
+    }
+
+    pub fn width(&self) -> usize {
+        self.cells.iter().map(Vec::len).max().unwrap_or(0)
+    }
+
+    pub fn height(&self) -> usize {
+        self.cells.len()
+    }
+}
+
+impl Map {
+    /// Writes every opaque cell of `sprite` with its top-left corner at
+    /// `origin`, registering `id` on each tile it covers. Cells past the
+    /// map's edges are dropped rather than clamped onto the border.
+    pub fn draw_sprite(
+        &mut self,
+        origin: (u16, u16),
+        sprite: &Sprite,
+        id: i64,
+    ) {
+        for (y, row) in sprite.cells.iter().enumerate() {
+            for (x, cell) in row.iter().enumerate() {
+                let Some((character, color)) = *cell else {
+                    continue;
+                };
+                let x = origin.0 as usize + x;
+                let y = origin.1 as usize + y;
+                if x < self.width && y < self.height {
+                    self.write((x as u16, y as u16), character, color, id);
+                }
+            }
+        }
+    }
+}
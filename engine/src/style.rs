@@ -0,0 +1,75 @@
+use crossterm::style::{Attribute, Attributes, Color};
+
+/// How a cell is drawn: its colors plus text attributes. The default is
+/// the terminal's own colors with no attributes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Style {
+    pub fg: Color,
+    pub bg: Color,
+    pub bold: bool,
+    pub underline: bool,
+    pub reverse: bool,
+    pub dim: bool,
+}
+
+impl Default for Style {
+    fn default() -> Self {
+        Style {
+            fg: Color::Reset,
+            bg: Color::Reset,
+            bold: false,
+            underline: false,
+            reverse: false,
+            dim: false,
+        }
+    }
+}
+
+impl Style {
+    pub fn new(fg: Color) -> Self {
+        Style {
+            fg,
+            ..Style::default()
+        }
+    }
+
+    pub fn background(mut self, bg: Color) -> Self {
+        self.bg = bg;
+        self
+    }
+
+    pub fn bold(mut self) -> Self {
+        self.bold = true;
+        self
+    }
+
+    pub fn underlined(mut self) -> Self {
+        self.underline = true;
+        self
+    }
+
+    pub fn reversed(mut self) -> Self {
+        self.reverse = true;
+        self
+    }
+
+    pub fn dimmed(mut self) -> Self {
+        self.dim = true;
+        self
+    }
+
+    pub fn attributes(&self) -> Attributes {
+        let mut attributes = Attributes::default();
+        for (enabled, attribute) in [
+            (self.bold, Attribute::Bold),
+            (self.underline, Attribute::Underlined),
+            (self.reverse, Attribute::Reverse),
+            (self.dim, Attribute::Dim),
+        ] {
+            if enabled {
+                attributes.set(attribute);
+            }
+        }
+        attributes
+    }
+}
@@ -0,0 +1,33 @@
+use std::mem;
+
+use crate::World;
+
+/// Game logic that isn't tied to one entity, e.g. gravity or scoring.
+/// Systems run once per update pass, after every entity's `update`.
+pub trait System {
+    fn run(&mut self, delta: f64, world: &mut World);
+}
+
+impl<F: FnMut(f64, &mut World)> System for F {
+    fn run(&mut self, delta: f64, world: &mut World) {
+        self(delta, world)
+    }
+}
+
+impl World {
+    /// Registers a system. Systems run in the order they were added; one
+    /// added by another system first runs next pass.
+    pub fn add_system(&mut self, system: impl System + 'static) {
+        self.systems.push(Box::new(system));
+    }
+
+    pub(crate) fn run_systems(&mut self, delta: f64) {
+        // Taken out while they run so each can borrow the world.
+        let mut systems = mem::take(&mut self.systems);
+        for system in systems.iter_mut() {
+            system.run(delta, self);
+        }
+        systems.append(&mut self.systems);
+        self.systems = systems;
+    }
+}
@@ -0,0 +1,38 @@
+use crate::World;
+
+impl World {
+    /// Adds `tag` to an entity; an entity can carry several tags. Tags are
+    /// dropped when the entity is removed.
+    pub fn set_tag(&mut self, id: i64, tag: &str) {
+        let ids = self.tags.entry(tag.to_string()).or_default();
+        if !ids.contains(&id) {
+            ids.push(id);
+        }
+    }
+
+    pub fn remove_tag(&mut self, id: i64, tag: &str) {
+        if let Some(ids) = self.tags.get_mut(tag) {
+            ids.retain(|&x| x != id);
+        }
+    }
+
+    pub fn has_tag(&self, id: i64, tag: &str) -> bool {
+        self.tags.get(tag).is_some_and(|x| x.contains(&id))
+    }
+
+    /// Every entity tagged `tag`, in the order they were tagged.
+    pub fn find_by_tag(&self, tag: &str) -> Vec<i64> {
+        self.tags.get(tag).cloned().unwrap_or_default()
+    }
+
+    /// The first entity tagged `tag`, e.g. "player".
+    pub fn find_one_by_tag(&self, tag: &str) -> Option<i64> {
+        self.tags.get(tag).and_then(|x| x.first().copied())
+    }
+
+    pub(crate) fn remove_tags(&mut self, id: i64) {
+        for ids in self.tags.values_mut() {
+            ids.retain(|&x| x != id);
+        }
+    }
+}
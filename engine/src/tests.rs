@@ -0,0 +1,522 @@
+use std::{
+    cell::{Cell, RefCell},
+    io::{self, BufWriter, Write},
+    mem,
+    rc::Rc,
+    time::Instant,
+};
+
+use crossterm::{event::KeyCode, style::Color};
+
+use crate::{
+    render::{BufferRenderer, Renderer},
+    save::{SaveError, SaveRegistry},
+    style::Style,
+    Entity, Map, RunWhilePaused, World,
+};
+
+fn headless(width: usize, height: usize) -> World {
+    World::new_with_renderer(
+        width,
+        height,
+        BufferRenderer::new(width as u16, height as u16),
+    )
+}
+
+// Draws the world the way the game loop does and returns the screen.
+fn present(world: &mut World) -> String {
+    world.draw();
+    world.ui.renderer_as::<BufferRenderer>().unwrap().text()
+}
+
+// Writes `@` `step` tiles further right every update.
+struct Walker {
+    x: u16,
+    step: u16,
+}
+
+impl Entity for Walker {
+    fn update(&mut self, _delta: f64, world: &mut World, id: i64) {
+        world.map.write((self.x, 0), '@', Color::White, id);
+        self.x += self.step;
+    }
+}
+
+#[test]
+fn paused_entities_do_not_pile_up_in_tiles() {
+    let mut world = headless(10, 1);
+    world.add_entity(Walker { x: 0, step: 1 });
+    world.add_entity(Walker { x: 2, step: 0 });
+    world.add_entity(Walker { x: 5, step: 1 });
+    world.set_component(1, RunWhilePaused);
+    world.set_component(2, RunWhilePaused);
+    world.tick(0.1);
+    world.set_paused(true);
+    for _ in 0..3 {
+        world.tick(0.1);
+    }
+    for x in 0..10 {
+        let contents = world.map.contents_at((x, 0));
+        assert!(contents.len() <= 1, "{x}: {contents:?}");
+    }
+    // The walker running while paused moved on without a trail, and the
+    // paused one stayed put.
+    assert_eq!(present(&mut world), "@ @     @ ");
+}
+
+#[test]
+fn map_is_repainted_under_closed_overlays() {
+    let mut world = headless(4, 2);
+    for x in 0..4 {
+        for y in 0..2 {
+            world.map.set_background((x, y), '.', Color::White);
+        }
+    }
+    assert_eq!(present(&mut world), "....\n....");
+    let _ = world.ui.draw_box((0, 0), (3, 2), Color::White, None);
+    assert_eq!(present(&mut world), "┌─┐.\n└─┘.");
+    let _ = world.ui.draw_box((0, 0), (3, 2), Color::White, None);
+    assert_eq!(present(&mut world), "┌─┐.\n└─┘.");
+    assert_eq!(present(&mut world), "....\n....");
+
+    let _ = world.ui.set_debug(true);
+    let _ = world.ui.debug_draw(1, "hi");
+    assert_eq!(present(&mut world), "....\nhi..");
+    let _ = world.ui.set_debug(false);
+    assert_eq!(present(&mut world), "....\n....");
+}
+
+#[test]
+fn viewport_follows_map_resize() {
+    let mut world = headless(3, 2);
+    assert_eq!(present(&mut world), "   \n   ");
+    world.map.resize(5, 3);
+    for x in 0..5 {
+        for y in 0..3 {
+            world.map.set_background((x, y), '.', Color::White);
+        }
+    }
+    assert_eq!(present(&mut world), ".....\n.....\n.....");
+    assert_eq!(world.camera.viewport, (5, 3));
+
+    world.load_level("ab\ncd", |_, _| None).unwrap();
+    present(&mut world);
+    assert_eq!(world.camera.viewport, (2, 2));
+
+    // A viewport set smaller than the map is left alone.
+    world.camera.viewport = (1, 1);
+    world.map.resize(4, 4);
+    present(&mut world);
+    assert_eq!(world.camera.viewport, (1, 1));
+}
+
+#[test]
+fn moving_entity_blanks_the_cell_it_left() {
+    let mut world = headless(4, 1);
+    world.add_entity(Walker { x: 0, step: 1 });
+    world.tick(0.1);
+    assert_eq!(present(&mut world), "@   ");
+    world.tick(0.1);
+    assert_eq!(present(&mut world), " @  ");
+    assert_eq!(world.render_to_string(), " @  ");
+}
+
+// Records its id each update.
+struct Counter {
+    log: Rc<RefCell<Vec<i64>>>,
+}
+
+impl Entity for Counter {
+    fn update(&mut self, _delta: f64, _world: &mut World, id: i64) {
+        self.log.borrow_mut().push(id);
+    }
+}
+
+// The fastest of a few runs of `frames` ticks over `count` entities.
+fn time_updates(count: usize, frames: usize) -> f64 {
+    let log = Rc::new(RefCell::new(Vec::new()));
+    let mut world = headless(1, 1);
+    for _ in 0..count {
+        world.add_entity(Counter { log: log.clone() });
+    }
+    (0..3)
+        .map(|_| {
+            let start = Instant::now();
+            for _ in 0..frames {
+                world.tick(0.01);
+            }
+            start.elapsed().as_secs_f64()
+        })
+        .fold(f64::INFINITY, f64::min)
+}
+
+#[test]
+fn thousand_entities_update_in_stable_order() {
+    let log = Rc::new(RefCell::new(Vec::new()));
+    let mut world = headless(1, 1);
+    for _ in 0..1000 {
+        world.add_entity(Counter { log: log.clone() });
+    }
+    for _ in 0..3 {
+        world.tick(0.01);
+        let order: Vec<i64> = log.borrow_mut().drain(..).collect();
+        assert_eq!(order, (0..1000).collect::<Vec<_>>());
+    }
+}
+
+#[test]
+fn update_time_scales_linearly() {
+    // Four times the entities should take about four times as long; a
+    // quadratic update would take sixteen.
+    let small = time_updates(1000, 20);
+    let large = time_updates(4000, 20);
+    assert!(large < small * 10.0, "{small}s for 1000, {large}s for 4000");
+}
+
+// Counts what reaches it, standing in for the terminal's file descriptor:
+// each `write` is one syscall.
+#[derive(Clone, Default)]
+struct Syscalls {
+    writes: Rc<Cell<usize>>,
+    flushes: Rc<Cell<usize>>,
+    bytes: Rc<Cell<usize>>,
+}
+
+impl Write for Syscalls {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.writes.set(self.writes.get() + 1);
+        self.bytes.set(self.bytes.get() + buf.len());
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.flushes.set(self.flushes.get() + 1);
+        Ok(())
+    }
+}
+
+#[test]
+fn full_redraw_is_written_in_few_syscalls() {
+    let syscalls = Syscalls::default();
+    // Buffered the way stdout is, so commands only hit the descriptor
+    // when the buffer fills or the frame is flushed.
+    let output = BufWriter::with_capacity(1024, syscalls.clone());
+    let mut world = World::new_with_output(80, 24, output);
+    for x in 0..80 {
+        for y in 0..24 {
+            let color = if (x + y) % 2 == 0 {
+                Color::Red
+            } else {
+                Color::Blue
+            };
+            world.map.set_background((x, y), '#', color);
+        }
+    }
+    world.draw();
+    let _ = world.ui.flush();
+    // Flushing per cell would cost at least one write per cell.
+    let (writes, bytes) = (syscalls.writes.get(), syscalls.bytes.get());
+    assert_eq!(syscalls.flushes.get(), 1);
+    assert!(
+        writes <= bytes / 1024 + 1,
+        "{writes} writes for {bytes} bytes"
+    );
+    assert!(writes < 80 * 24 / 10);
+}
+
+// Which entity did what, in which frame.
+type SpawnLog = Rc<RefCell<Vec<(&'static str, &'static str, u64)>>>;
+
+// Logs its `start` and `update` calls with the frame they ran in.
+struct Child {
+    name: &'static str,
+    log: SpawnLog,
+}
+
+impl Entity for Child {
+    fn start(&mut self, world: &mut World, _id: i64) {
+        let frame = world.frame_count();
+        self.log.borrow_mut().push((self.name, "start", frame));
+    }
+    fn update(&mut self, _delta: f64, world: &mut World, _id: i64) {
+        let frame = world.frame_count();
+        self.log.borrow_mut().push((self.name, "update", frame));
+    }
+}
+
+// Spawns one child each way on its first update.
+struct Spawner {
+    log: SpawnLog,
+    spawned: bool,
+}
+
+impl Entity for Spawner {
+    fn update(&mut self, _delta: f64, world: &mut World, _id: i64) {
+        if mem::replace(&mut self.spawned, true) {
+            return;
+        }
+        let log = self.log.clone();
+        world.add_entity(Child { name: "added", log });
+        let log = self.log.clone();
+        world.spawn_immediate(Child {
+            name: "immediate",
+            log,
+        });
+    }
+}
+
+#[test]
+fn spawn_timing_during_update() {
+    let log = Rc::new(RefCell::new(Vec::new()));
+    let mut world = headless(1, 1);
+    world.add_entity(Spawner {
+        log: log.clone(),
+        spawned: false,
+    });
+    world.tick(0.1);
+    assert_eq!(*log.borrow(), [("immediate", "start", 1)]);
+    world.tick(0.1);
+    assert_eq!(
+        *log.borrow(),
+        [
+            ("immediate", "start", 1),
+            ("added", "start", 2),
+            ("added", "update", 2),
+            ("immediate", "update", 2),
+        ]
+    );
+}
+
+// Writes `character` at the origin, removing itself once `lives` runs out.
+struct Mortal {
+    character: char,
+    lives: u32,
+}
+
+impl Entity for Mortal {
+    fn update(&mut self, _delta: f64, world: &mut World, id: i64) {
+        world.map.write((0, 0), self.character, Color::White, id);
+        if self.lives == 0 {
+            world.remove_entity(id);
+        }
+        self.lives = self.lives.saturating_sub(1);
+    }
+}
+
+#[test]
+fn killed_entity_is_not_drawn_on_its_death_frame() {
+    let mut world = headless(2, 1);
+    world.add_entity(Mortal {
+        character: 'a',
+        lives: u32::MAX,
+    });
+    world.add_entity(Mortal {
+        character: 'b',
+        lives: 1,
+    });
+    world.set_z_index(1, 1);
+    world.tick(0.1);
+    assert_eq!(present(&mut world), "b ");
+    // `b` dies this frame, uncovering `a` rather than a blank tile.
+    world.tick(0.1);
+    assert_eq!(present(&mut world), "a ");
+    assert_eq!(world.map.contents_at((0, 0)), [0]);
+}
+
+// Counts the cells drawn, for checking how much a frame redraws.
+#[derive(Default)]
+struct CellCounter {
+    cells: usize,
+}
+
+impl Renderer for CellCounter {
+    fn draw_cell(
+        &mut self,
+        _: (u16, u16),
+        _: char,
+        _: Style,
+    ) -> io::Result<()> {
+        self.cells += 1;
+        Ok(())
+    }
+
+    fn clear(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn clear_line(&mut self, _row: u16) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn static_map_only_redraws_what_changed() {
+    let (width, height) = (400, 200);
+    let mut world =
+        World::new_with_renderer(width, height, CellCounter::default());
+    for x in 0..width as u16 {
+        for y in 0..height as u16 {
+            world.map.set_background((x, y), '.', Color::DarkGrey);
+        }
+    }
+    world.add_entity(Walker { x: 0, step: 1 });
+    let mut drawn = Vec::new();
+    for _ in 0..50 {
+        world.tick(0.1);
+        world.draw();
+        drawn.push(world.ui.renderer_as::<CellCounter>().unwrap().cells);
+    }
+    assert_eq!(drawn[0], width * height);
+    // After the first frame only the walker's old and new tiles change.
+    for frames in drawn.windows(2) {
+        assert_eq!(frames[1] - frames[0], 2);
+    }
+}
+
+#[test]
+fn clear_and_restart_blank_the_screen() {
+    let mut world = headless(4, 1);
+    world.add_entity(Walker { x: 2, step: 0 });
+    world.tick(0.1);
+    assert_eq!(present(&mut world), "  @ ");
+    world.clear(false);
+    assert_eq!(present(&mut world), "    ");
+
+    world.on_setup(|world| world.add_entity(Walker { x: 1, step: 0 }));
+    world.restart();
+    world.tick(0.1);
+    assert_eq!(present(&mut world), " @  ");
+    world.on_setup(|_| {});
+    world.restart();
+    world.tick(0.1);
+    assert_eq!(present(&mut world), "    ");
+}
+
+#[test]
+fn full_redraw_keeps_ui_drawn_earlier_in_the_frame() {
+    let mut world = headless(4, 1);
+    assert_eq!(present(&mut world), "    ");
+    let _ = world.ui.draw_text((0, 0), "hi", Color::White);
+    world.map.resize(4, 1);
+    assert_eq!(present(&mut world), "hi  ");
+    assert_eq!(present(&mut world), "    ");
+}
+
+#[test]
+fn shake_moves_the_view_off_a_map_the_size_of_the_viewport() {
+    let mut world = headless(4, 2);
+    for (y, row) in ["abcd", "efgh"].into_iter().enumerate() {
+        for (x, character) in row.chars().enumerate() {
+            let position = (x as u16, y as u16);
+            world.map.set_background(position, character, Color::White);
+        }
+    }
+    assert_eq!(present(&mut world), "abcd\nefgh");
+    world.seed_rng(3);
+    world.shake(3.0, 10.0);
+    world.tick(0.1);
+    assert_eq!(world.view_position(), (2, -1));
+    assert_eq!(present(&mut world), "    \ncd  ");
+    world.tick(10.0);
+    assert_eq!(present(&mut world), "abcd\nefgh");
+}
+
+// Records the keys each update saw.
+struct KeyLog {
+    seen: Vec<Vec<KeyCode>>,
+}
+
+impl Entity for KeyLog {
+    fn update(&mut self, _delta: f64, world: &mut World, _id: i64) {
+        self.seen.push(world.ui.frame_inputs.clone());
+    }
+}
+
+#[test]
+fn fixed_timestep_keeps_keys_from_frames_without_a_pass() {
+    let mut world = headless(1, 1);
+    world.set_fixed_timestep(0.1);
+    world.add_entity(KeyLog { seen: Vec::new() });
+    world.tick_with_input(0.06, vec![KeyCode::Char('a')]);
+    world.tick_with_input(0.06, vec![KeyCode::Char('b')]);
+    world.tick_with_input(0.06, vec![]);
+    let seen = &world.get_entity_as::<KeyLog>(0).unwrap().seen;
+    assert_eq!(seen, &[vec![KeyCode::Char('a'), KeyCode::Char('b')]]);
+    assert_eq!(world.ui.current_input, None);
+}
+
+#[test]
+#[should_panic(expected = "map needs at least one tile")]
+fn map_rejects_zero_width() {
+    Map::new(0, 3);
+}
+
+#[test]
+#[should_panic(expected = "map needs at least one tile")]
+fn resize_rejects_zero_height() {
+    headless(4, 2).map.resize(4, 0);
+}
+
+#[test]
+fn load_rejects_a_save_without_tiles() {
+    let registry = SaveRegistry::new();
+    let mut world = headless(4, 2);
+    let save = r#"{"width":3,"height":0,"next_id":0,"entities":[],"tags":{}}"#;
+    let result = world.load(save.as_bytes(), &registry);
+    assert!(matches!(&result, Err(SaveError::Json(_))));
+    assert!(result.unwrap_err().to_string().contains("no tiles"));
+    assert_eq!(world.map_size(), (4, 2));
+}
+
+// Records how many entities it finds on a tile this frame and last frame.
+struct Prober {
+    position: (u16, u16),
+    found: Rc<RefCell<Vec<(usize, usize)>>>,
+}
+
+impl Entity for Prober {
+    fn update(&mut self, _delta: f64, world: &mut World, _id: i64) {
+        let current = world.query_map_current(self.position).len();
+        let previous = world.query_map(self.position).len();
+        self.found.borrow_mut().push((current, previous));
+    }
+}
+
+#[test]
+fn query_map_current_sees_targets_updated_earlier_this_frame() {
+    let found = Rc::new(RefCell::new(Vec::new()));
+    let prober = || Prober {
+        position: (1, 0),
+        found: found.clone(),
+    };
+
+    // The target updates first, so its write this frame is visible.
+    let mut world = headless(4, 1);
+    world.add_entity(Walker { x: 1, step: 0 });
+    world.add_entity(prober());
+    world.tick(0.1);
+    world.tick(0.1);
+    assert_eq!(mem::take(&mut *found.borrow_mut()), [(1, 0), (1, 1)]);
+
+    // The target updates after, so only last frame's write is there.
+    let mut world = headless(4, 1);
+    world.add_entity(prober());
+    world.add_entity(Walker { x: 1, step: 0 });
+    world.tick(0.1);
+    world.tick(0.1);
+    assert_eq!(mem::take(&mut *found.borrow_mut()), [(0, 0), (0, 1)]);
+}
+
+#[test]
+fn fill_rect_stops_at_the_map_edge() {
+    let mut world = headless(4, 2);
+    world
+        .map
+        .fill_rect((2, 1), (u16::MAX, u16::MAX), '#', Color::White, 0);
+    assert_eq!(world.map.contents_at((3, 1)), [0]);
+    assert_eq!(present(&mut world), "    \n  ##");
+}
@@ -0,0 +1,125 @@
+/// Counts down a fixed duration, e.g. a weapon reload or a spawn interval.
+pub struct Timer {
+    duration: f64,
+    remaining: f64,
+    repeating: bool,
+    // Whether a one-shot timer has fired since it was last reset.
+    fired: bool,
+}
+
+impl Timer {
+    /// A timer that fires every `duration` seconds. Panics unless
+    /// `duration` is positive.
+    pub fn new(duration: f64) -> Self {
+        assert!(duration > 0.0, "repeating timer needs a positive duration");
+        Timer {
+            duration,
+            remaining: duration,
+            repeating: true,
+            fired: false,
+        }
+    }
+
+    /// A timer that fires once and then stays ready until `reset`. A zero
+    /// duration is ready at once and fires on the first tick. Panics if
+    /// `duration` is negative.
+    pub fn one_shot(duration: f64) -> Self {
+        assert!(
+            duration >= 0.0,
+            "one-shot timer needs a duration of 0 or more"
+        );
+        Timer {
+            duration,
+            remaining: duration,
+            repeating: false,
+            fired: false,
+        }
+    }
+
+    /// Advances the timer and returns true on the tick it fires. Repeating
+    /// timers start the next countdown straight away; a `delta` spanning
+    /// several periods fires once and skips ahead to the current one.
+    pub fn tick(&mut self, delta: f64) -> bool {
+        if self.fired {
+            return false;
+        }
+        self.remaining -= delta;
+        if self.remaining > 0.0 {
+            return false;
+        }
+        if self.repeating {
+            while self.remaining <= 0.0 {
+                self.remaining += self.duration;
+            }
+        } else {
+            self.remaining = 0.0;
+            self.fired = true;
+        }
+        true
+    }
+
+    pub fn is_ready(&self) -> bool {
+        self.remaining <= 0.0
+    }
+
+    pub fn remaining(&self) -> f64 {
+        self.remaining
+    }
+
+    pub fn duration(&self) -> f64 {
+        self.duration
+    }
+
+    /// Starts the countdown again from the full duration.
+    pub fn reset(&mut self) {
+        self.remaining = self.duration;
+        self.fired = false;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Timer;
+
+    #[test]
+    fn repeating_timer_keeps_firing_after_a_long_frame() {
+        let mut timer = Timer::new(1.0);
+        assert!(timer.tick(2.5));
+        assert!((timer.remaining() - 0.5).abs() < 1e-9);
+        assert!(!timer.tick(0.25));
+        assert!(timer.tick(0.25));
+        assert!(timer.tick(10.0));
+    }
+
+    #[test]
+    #[should_panic]
+    fn repeating_timer_rejects_zero_duration() {
+        Timer::new(0.0);
+    }
+
+    #[test]
+    fn one_shot_timer_fires_once() {
+        let mut timer = Timer::one_shot(1.0);
+        assert!(timer.tick(3.0));
+        assert!(!timer.tick(3.0));
+        timer.reset();
+        assert!(timer.tick(1.0));
+    }
+
+    #[test]
+    fn zero_length_one_shot_fires_once() {
+        let mut timer = Timer::one_shot(0.0);
+        assert!(timer.is_ready());
+        assert!(timer.tick(0.1));
+        assert!(!timer.tick(0.1));
+        timer.reset();
+        assert!(timer.tick(0.0));
+        assert!(!timer.tick(0.0));
+    }
+
+    #[test]
+    #[should_panic]
+    fn one_shot_rejects_negative_duration() {
+        Timer::one_shot(-1.0);
+    }
+}
@@ -1,76 +1,520 @@
 use std::{
-    io::{self, Stdout},
-    sync::mpsc::{self, Receiver},
-    thread,
+    any::Any,
+    collections::{HashMap, VecDeque},
+    fs::{File, OpenOptions},
+    io::{self, Write},
+    mem,
+    path::Path,
+    time::{Duration, Instant},
 };
 
+use crate::{
+    palette::Palette,
+    render::{CrosstermRenderer, Renderer},
+    style::Style,
+    unicode,
+};
 use crossterm::{
-    cursor,
-    event::{read, Event, KeyCode},
-    style::{self, Color, Stylize},
-    terminal, QueueableCommand,
+    event::{poll, read, Event, KeyCode, KeyEventKind, MouseEvent},
+    style::Color,
 };
+
+pub mod input;
+pub mod menu;
+
 pub struct UI {
-    pub stdout: Stdout,
+    pub renderer: Box<dyn Renderer>,
+    /// The first key received this frame.
     pub current_input: Option<KeyCode>,
+    /// The most recent key received in any frame so far.
     pub last_input: Option<KeyCode>,
-    input_reciever: Receiver<Option<KeyCode>>,
+    /// Every key received since the previous frame, oldest first.
+    pub frame_inputs: Vec<KeyCode>,
+    /// The most recent mouse event this frame, if mouse capture is on.
+    pub current_mouse: Option<MouseEvent>,
+    /// Applied to every color drawn; see `World::set_palette`.
+    pub palette: Palette,
+    pub(crate) mouse_enabled: bool,
+    /// Set when the terminal was resized; `World::draw` clears it after
+    /// redrawing everything.
+    pub(crate) resized: bool,
+    terminal_size: Option<(u16, u16)>,
+    input_queue: VecDeque<KeyCode>,
+    debug: bool,
+    // Rows written by `debug_draw`, blanked when debug drawing is turned
+    // off.
+    debug_rows: Vec<u16>,
+    /// Cells drawn and rows blanked straight on the terminal since the
+    /// last `World::draw`, which repaints the map there once they stop.
+    pub(crate) drawn_cells: Vec<((u16, u16), char, Style)>,
+    pub(crate) cleared_rows: Vec<u16>,
+    last_input_time: Option<Instant>,
+    // When each key was last pressed, and the press before that.
+    press_times: HashMap<KeyCode, (Instant, Option<Instant>)>,
+    // Where `log` writes, and when it was opened.
+    log_file: Option<(File, Instant)>,
 }
+
+impl Default for UI {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl UI {
     pub fn new() -> UI {
-        let (tx, rx) = mpsc::channel();
+        UI::with_renderer(Box::new(CrosstermRenderer::new()))
+    }
 
-        thread::spawn(move || loop {
-            tx.send(read_inputs()).unwrap();
-        });
+    pub fn with_renderer(renderer: Box<dyn Renderer>) -> UI {
         UI {
-            stdout: io::stdout(),
+            terminal_size: renderer.size(),
+            renderer,
             current_input: None,
             last_input: None,
-            input_reciever: rx,
+            last_input_time: None,
+            press_times: HashMap::new(),
+            frame_inputs: Vec::new(),
+            current_mouse: None,
+            palette: Palette::identity(),
+            mouse_enabled: false,
+            resized: false,
+            input_queue: VecDeque::new(),
+            debug: false,
+            debug_rows: Vec::new(),
+            drawn_cells: Vec::new(),
+            cleared_rows: Vec::new(),
+            log_file: None,
         }
     }
 
+    /// Queues one cell; nothing is shown until the frame's flush.
     pub fn terminal_draw(
         &mut self,
         position: (u16, u16),
         character: char,
         color: Color,
     ) -> io::Result<()> {
-        self.stdout
-            .queue(cursor::MoveTo(position.0, position.1))?
-            .queue(style::PrintStyledContent((character).with(color)))?;
+        self.terminal_draw_bg(position, character, color, Color::Reset)
+    }
+
+    /// Like `terminal_draw` with a background color. `Color::Reset` is the
+    /// terminal's own background.
+    pub fn terminal_draw_bg(
+        &mut self,
+        position: (u16, u16),
+        character: char,
+        color: Color,
+        background: Color,
+    ) -> io::Result<()> {
+        self.terminal_draw_styled(
+            position,
+            character,
+            Style::new(color).background(background),
+        )
+    }
+
+    /// Like `terminal_draw` with a whole style. Cells drawn over the map
+    /// this way stay until a frame goes by without them being drawn again,
+    /// and then the map is repainted there.
+    pub fn terminal_draw_styled(
+        &mut self,
+        position: (u16, u16),
+        character: char,
+        style: Style,
+    ) -> io::Result<()> {
+        self.drawn_cells.push((position, character, style));
+        self.draw_cell(position, character, style)
+    }
+
+    // Draws this frame's `drawn_cells` again, after the terminal was
+    // cleared under them.
+    pub(crate) fn redraw_cells(&mut self) -> io::Result<()> {
+        for (position, character, style) in self.drawn_cells.clone() {
+            self.draw_cell(position, character, style)?;
+        }
+        Ok(())
+    }
+
+    // Draws a cell for the map, which keeps track of it itself.
+    pub(crate) fn draw_cell(
+        &mut self,
+        position: (u16, u16),
+        character: char,
+        mut style: Style,
+    ) -> io::Result<()> {
+        style.fg = self.palette.apply(style.fg);
+        style.bg = self.palette.apply(style.bg);
+        self.renderer.draw_cell(position, character, style)
+    }
+
+    /// Writes `text` straight to the terminal, e.g. for a HUD outside the
+    /// map. Each character takes its `unicode::char_width` in columns, so
+    /// wide characters take two. Text over the map has to be drawn every
+    /// frame to stay; see `terminal_draw_styled`.
+    pub fn draw_text(
+        &mut self,
+        position: (u16, u16),
+        text: &str,
+        color: Color,
+    ) -> io::Result<()> {
+        let mut x = position.0;
+        for character in text.chars() {
+            self.terminal_draw((x, position.1), character, color)?;
+            x = x.saturating_add(unicode::char_width(character));
+        }
+        Ok(())
+    }
+
+    /// Draws `text` centered in the `width` columns of `row` starting at
+    /// column 0. Text too long to fit is cut short with an ellipsis.
+    pub fn draw_text_centered(
+        &mut self,
+        row: u16,
+        width: u16,
+        text: &str,
+        color: Color,
+    ) -> io::Result<()> {
+        let width = width as usize;
+        let length = text.chars().count();
+        let text: String = if length > width {
+            let kept = width.saturating_sub(1);
+            let mut cut: String = text.chars().take(kept).collect();
+            if width > 0 {
+                cut.push('…');
+            }
+            cut
+        } else {
+            text.to_string()
+        };
+        let x = width.saturating_sub(unicode::str_width(&text)) / 2;
+        self.draw_text((x as u16, row), &text, color)
+    }
+
+    /// Word-wraps `text` into the rectangle at `top_left`, one line per
+    /// row. Newlines in `text` are kept, words longer than a row are split,
+    /// and lines below the rectangle are dropped.
+    pub fn draw_text_wrapped(
+        &mut self,
+        top_left: (u16, u16),
+        size: (u16, u16),
+        text: &str,
+        color: Color,
+    ) -> io::Result<()> {
+        let lines = wrap(text, size.0 as usize);
+        for (i, line) in lines.iter().take(size.1 as usize).enumerate() {
+            let y = top_left.1.saturating_add(i as u16);
+            self.draw_text((top_left.0, y), line, color)?;
+        }
+        Ok(())
+    }
+
+    /// Outlines a rectangle with box-drawing characters, leaving the
+    /// inside alone, with `title` set into the top border. Cells off the
+    /// terminal are skipped. Boxes under 2×2 draw nothing.
+    pub fn draw_box(
+        &mut self,
+        top_left: (u16, u16),
+        size: (u16, u16),
+        color: Color,
+        title: Option<&str>,
+    ) -> io::Result<()> {
+        if size.0 < 2 || size.1 < 2 {
+            return Ok(());
+        }
+        let (left, top) = (top_left.0 as u32, top_left.1 as u32);
+        let (right, bottom) =
+            (left + size.0 as u32 - 1, top + size.1 as u32 - 1);
+        for x in left..=right {
+            let (upper, lower) = if x == left {
+                ('┌', '└')
+            } else if x == right {
+                ('┐', '┘')
+            } else {
+                ('─', '─')
+            };
+            self.draw_clipped((x, top), upper, color)?;
+            self.draw_clipped((x, bottom), lower, color)?;
+        }
+        for y in top + 1..bottom {
+            self.draw_clipped((left, y), '│', color)?;
+            self.draw_clipped((right, y), '│', color)?;
+        }
+        if let Some(title) = title {
+            let room = size.0.saturating_sub(4) as usize;
+            for (i, character) in title.chars().take(room).enumerate() {
+                self.draw_clipped(
+                    (left + 2 + i as u32, top),
+                    character,
+                    color,
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Like `draw_box`, also blanking the inside.
+    pub fn draw_box_filled(
+        &mut self,
+        top_left: (u16, u16),
+        size: (u16, u16),
+        color: Color,
+        title: Option<&str>,
+    ) -> io::Result<()> {
+        self.draw_box(top_left, size, color, title)?;
+        let (left, top) = (top_left.0 as u32, top_left.1 as u32);
+        for x in left + 1..left + size.0.saturating_sub(1) as u32 {
+            for y in top + 1..top + size.1.saturating_sub(1) as u32 {
+                self.draw_clipped((x, y), ' ', color)?;
+            }
+        }
+        Ok(())
+    }
+
+    // Draws one cell unless it is off the terminal.
+    fn draw_clipped(
+        &mut self,
+        position: (u32, u32),
+        character: char,
+        color: Color,
+    ) -> io::Result<()> {
+        let (columns, rows) =
+            self.terminal_size.unwrap_or((u16::MAX, u16::MAX));
+        if position.0 >= columns as u32 || position.1 >= rows as u32 {
+            return Ok(());
+        }
+        let position = (position.0 as u16, position.1 as u16);
+        self.terminal_draw(position, character, color)
+    }
+
+    /// Draws a horizontal bar `width` cells long, the first `fraction` of
+    /// it (rounded to whole cells, clamped to 0..=1) in `filled` and the
+    /// rest in `empty`, e.g. for health or reload progress.
+    pub fn draw_bar(
+        &mut self,
+        position: (u16, u16),
+        width: u16,
+        fraction: f64,
+        filled: Color,
+        empty: Color,
+    ) -> io::Result<()> {
+        let fraction = if fraction.is_nan() { 0.0 } else { fraction };
+        let full = (fraction.clamp(0.0, 1.0) * width as f64).round() as u16;
+        for i in 0..width {
+            let color = if i < full { filled } else { empty };
+            let x = position.0.saturating_add(i);
+            self.terminal_draw((x, position.1), '█', color)?;
+        }
         Ok(())
     }
 
+    /// Resets colors and blanks the whole terminal.
+    pub fn clear(&mut self) -> io::Result<()> {
+        self.renderer.clear()
+    }
+
+    /// Turns `debug_draw` on or off; it is off by default. Turning it off
+    /// blanks the rows it drew, and the next draw repaints the map there.
+    /// `World::init` toggles it on F3.
+    pub fn set_debug(&mut self, enabled: bool) -> io::Result<()> {
+        self.debug = enabled;
+        if !enabled {
+            for row in mem::take(&mut self.debug_rows) {
+                self.clear_line(row)?;
+            }
+        }
+        Ok(())
+    }
+
+    pub fn is_debug(&self) -> bool {
+        self.debug
+    }
+
+    /// Writes `text` on its own terminal row, for on-screen stats while
+    /// developing. Does nothing unless enabled with `set_debug`.
     pub fn debug_draw(&mut self, line: u16, text: &str) -> io::Result<()> {
-        self.stdout
-            .queue(cursor::MoveTo(0, line))?
-            .queue(terminal::Clear(terminal::ClearType::CurrentLine))?
-            .queue(style::PrintStyledContent((text).with(Color::Red)))?;
+        if !self.debug {
+            return Ok(());
+        }
+        if !self.debug_rows.contains(&line) {
+            self.debug_rows.push(line);
+        }
+        self.clear_line(line)?;
+        self.draw_text((0, line), text, Color::Red)
+    }
+
+    // Blanks a row, which the next draw repaints the map over.
+    fn clear_line(&mut self, row: u16) -> io::Result<()> {
+        self.cleared_rows.push(row);
+        self.renderer.clear_line(row)
+    }
+
+    /// Sounds the terminal bell (ASCII BEL) with the next flush. Whether
+    /// it beeps, flashes or does nothing is up to the user's terminal.
+    pub fn beep(&mut self) -> io::Result<()> {
+        self.renderer.beep()
+    }
+
+    /// Sends `log` output to `path`, appending to it if it exists.
+    pub fn set_log_file(&mut self, path: impl AsRef<Path>) -> io::Result<()> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        self.log_file = Some((file, Instant::now()));
+        Ok(())
+    }
+
+    /// Appends `message` to the log file as one line, stamped with the
+    /// seconds since the file was set. Does nothing without a log file.
+    /// Unlike `debug_draw`, this keeps diagnostics off the screen and
+    /// survives the game exiting.
+    pub fn log(&mut self, message: &str) -> io::Result<()> {
+        if let Some((file, opened)) = &mut self.log_file {
+            let seconds = opened.elapsed().as_secs_f64();
+            writeln!(file, "[{seconds:9.3}] {message}")?;
+        }
         Ok(())
     }
 
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.renderer.flush()
+    }
+
+    /// The renderer as its concrete type, e.g. to read back a
+    /// `BufferRenderer` in a test.
+    pub fn renderer_as<T: Renderer>(&self) -> Option<&T> {
+        let renderer: &dyn Any = self.renderer.as_ref();
+        renderer.downcast_ref::<T>()
+    }
+
+    /// Reads every pending terminal event without blocking and appends
+    /// key presses to the input queue. A resize is recorded so the next
+    /// frame is redrawn from scratch.
     pub fn update_input(&mut self) {
-        self.current_input = match self.input_reciever.try_recv() {
-            Ok(ko) => match ko {
-                Some(k) => {
-                    self.last_input = Some(k);
-                    Some(k)
+        while let Ok(true) = poll(Duration::ZERO) {
+            match read() {
+                // Windows also reports key releases; only presses and
+                // auto-repeats are input.
+                Ok(Event::Key(event))
+                    if event.kind != KeyEventKind::Release =>
+                {
+                    self.input_queue.push_back(event.code)
                 }
-                None => self.current_input,
-            },
-            Err(_) => self.current_input,
+                Ok(Event::Mouse(event)) => self.current_mouse = Some(event),
+                Ok(Event::Resize(width, height)) => {
+                    self.terminal_size = Some((width, height));
+                    self.resized = true;
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// The terminal's size in cells, or `None` if the renderer has no
+    /// fixed size. Kept up to date by `update_input`.
+    pub fn terminal_size(&self) -> Option<(u16, u16)> {
+        self.terminal_size
+    }
+
+    /// The terminal cell under the mouse for this frame's mouse event. Use
+    /// `World::mouse_tile` to get the map tile under it.
+    pub fn mouse_cell(&self) -> Option<(u16, u16)> {
+        self.current_mouse.map(|x| (x.column, x.row))
+    }
+
+    /// Adds `inputs` to this frame's keys, after any still waiting for an
+    /// update pass, updating `current_input`, `last_input` and the input
+    /// timestamp together.
+    pub fn set_frame_inputs(&mut self, inputs: Vec<KeyCode>) {
+        if self.current_input.is_none() {
+            self.current_input = inputs.first().copied();
+        }
+        let now = Instant::now();
+        if let Some(&last) = inputs.last() {
+            self.last_input = Some(last);
+            self.last_input_time = Some(now);
+        }
+        for &key in &inputs {
+            let previous = self.press_times.get(&key).map(|x| x.0);
+            self.press_times.insert(key, (now, previous));
+        }
+        self.frame_inputs.extend(inputs);
+    }
+
+    /// True on the frame `key` is pressed for the second time within
+    /// `window`, e.g. for a dash. Firing uses up both presses, so a third
+    /// quick press starts a new double tap rather than completing
+    /// another. Held keys auto-repeat in most terminals, which also counts.
+    pub fn is_double_tap(&mut self, key: KeyCode, window: Duration) -> bool {
+        if !self.frame_inputs.contains(&key) {
+            return false;
+        }
+        let Some((latest, previous)) = self.press_times.get_mut(&key) else {
+            return false;
         };
+        match previous {
+            Some(previous) if latest.duration_since(*previous) <= window => {
+                self.press_times.remove(&key);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// The most recent key received, same as the `last_input` field.
+    pub fn last_input(&self) -> Option<KeyCode> {
+        self.last_input
+    }
+
+    /// When `last_input` was handed to a frame, accurate to the frame, or
+    /// `None` if no key has been pressed yet.
+    pub fn last_input_time(&self) -> Option<Instant> {
+        self.last_input_time
+    }
+
+    /// How long since the last key press, e.g. to start an attract screen
+    /// when idle. `None` if no key has been pressed yet.
+    pub fn since_last_input(&self) -> Option<Duration> {
+        self.last_input_time.map(|x| x.elapsed())
+    }
+
+    /// Takes every queued key in FIFO order: keys come out in the order
+    /// they were pressed, and the queue is left empty.
+    pub fn drain_inputs(&mut self) -> Vec<KeyCode> {
+        self.input_queue.drain(..).collect()
     }
 }
 
-fn read_inputs() -> Option<KeyCode> {
-    match read() {
-        Ok(event) => match event {
-            Event::Key(event) => Some(event.code),
-            _ => None,
-        },
-        _ => None,
+// Greedy word wrap to lines of at most `width` characters.
+fn wrap(text: &str, width: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    if width == 0 {
+        return lines;
+    }
+    for paragraph in text.lines() {
+        let mut line = String::new();
+        let mut length = 0;
+        for word in paragraph.split_whitespace() {
+            let mut word: Vec<char> = word.chars().collect();
+            if length > 0 && length + 1 + word.len() > width {
+                lines.push(mem::take(&mut line));
+                length = 0;
+            }
+            while word.len() > width {
+                let rest = word.split_off(width);
+                if length > 0 {
+                    lines.push(mem::take(&mut line));
+                    length = 0;
+                }
+                lines.push(word.into_iter().collect());
+                word = rest;
+            }
+            if length > 0 {
+                line.push(' ');
+                length += 1;
+            }
+            length += word.len();
+            line.extend(word);
+        }
+        lines.push(line);
     }
+    lines
 }
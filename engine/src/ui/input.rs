@@ -0,0 +1,47 @@
+use std::collections::HashMap;
+
+use crossterm::event::KeyCode;
+
+use super::UI;
+
+/// Maps named actions (e.g. "move_left") to the keys that trigger them, so
+/// an action can have several bindings and be rebound without touching
+/// entity code.
+#[derive(Default)]
+pub struct InputMap {
+    bindings: HashMap<String, Vec<KeyCode>>,
+}
+
+impl InputMap {
+    pub fn new() -> Self {
+        InputMap {
+            bindings: HashMap::new(),
+        }
+    }
+
+    /// Adds `key` as a trigger for `action`, keeping existing bindings.
+    pub fn bind(&mut self, action: &str, key: KeyCode) {
+        let keys = self.bindings.entry(action.to_string()).or_default();
+        if !keys.contains(&key) {
+            keys.push(key);
+        }
+    }
+
+    /// Removes every binding for `action`.
+    pub fn unbind(&mut self, action: &str) {
+        self.bindings.remove(action);
+    }
+
+    pub fn keys(&self, action: &str) -> &[KeyCode] {
+        match self.bindings.get(action) {
+            Some(keys) => keys,
+            None => &[],
+        }
+    }
+
+    /// True when any key bound to `action` was pressed this frame.
+    pub fn is_action_active(&self, action: &str, ui: &UI) -> bool {
+        let keys = self.keys(action);
+        ui.frame_inputs.iter().any(|x| keys.contains(x))
+    }
+}
@@ -0,0 +1,67 @@
+use std::io;
+
+use crossterm::{event::KeyCode, style::Color};
+
+use super::UI;
+use crate::style::Style;
+
+/// A vertical list of options, e.g. a title screen or pause menu. Up and
+/// Down move the selection, wrapping at the ends, and Enter picks it.
+pub struct Menu {
+    pub items: Vec<String>,
+    selected: usize,
+}
+
+impl Menu {
+    pub fn new(items: &[&str]) -> Self {
+        Menu {
+            items: items.iter().map(|x| x.to_string()).collect(),
+            selected: 0,
+        }
+    }
+
+    pub fn selected(&self) -> usize {
+        self.selected
+    }
+
+    /// Moves the selection or confirms it. Returns the selected index when
+    /// `key` is Enter.
+    pub fn handle_input(&mut self, key: KeyCode) -> Option<usize> {
+        let count = self.items.len();
+        if count == 0 {
+            return None;
+        }
+        match key {
+            KeyCode::Up => self.selected = (self.selected + count - 1) % count,
+            KeyCode::Down => self.selected = (self.selected + 1) % count,
+            KeyCode::Enter => return Some(self.selected),
+            _ => {}
+        }
+        None
+    }
+
+    /// Draws one item per row from `origin` down, the selected one in
+    /// reverse video. Shorter items are padded to the longest, so the
+    /// highlight is a uniform bar.
+    pub fn draw(
+        &self,
+        ui: &mut UI,
+        origin: (u16, u16),
+        color: Color,
+    ) -> io::Result<()> {
+        let width = self.items.iter().map(|x| x.chars().count()).max();
+        for (i, item) in self.items.iter().enumerate() {
+            let mut style = Style::new(color);
+            if i == self.selected {
+                style = style.reversed();
+            }
+            let text = format!("{item:<0$}", width.unwrap_or(0));
+            let y = origin.1.saturating_add(i as u16);
+            for (j, character) in text.chars().enumerate() {
+                let x = origin.0.saturating_add(j as u16);
+                ui.terminal_draw_styled((x, y), character, style)?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,51 @@
+/// How many terminal columns `character` takes: 2 for East Asian wide
+/// and fullwidth characters and most emoji, 0 for combining marks and
+/// other zero-width characters, 1 for everything else. This covers the
+/// common ranges rather than the full Unicode width tables, so rare
+/// characters may be measured as narrow.
+pub fn char_width(character: char) -> u16 {
+    let code = character as u32;
+    if ZERO_WIDTH.iter().any(|x| x.contains(&code)) {
+        0
+    } else if WIDE.iter().any(|x| x.contains(&code)) {
+        2
+    } else {
+        1
+    }
+}
+
+/// The columns `text` takes, the sum of its characters' `char_width`.
+pub fn str_width(text: &str) -> usize {
+    text.chars().map(|x| char_width(x) as usize).sum()
+}
+
+const ZERO_WIDTH: [std::ops::RangeInclusive<u32>; 8] = [
+    0x0300..=0x036F, // Combining diacritical marks
+    0x0483..=0x0489, // Combining Cyrillic
+    0x1AB0..=0x1AFF, // Combining diacritical marks extended
+    0x1DC0..=0x1DFF, // Combining diacritical marks supplement
+    0x200B..=0x200F, // Zero-width space, joiners and direction marks
+    0x20D0..=0x20FF, // Combining marks for symbols
+    0xFE00..=0xFE0F, // Variation selectors
+    0xFE20..=0xFE2F, // Combining half marks
+];
+
+const WIDE: [std::ops::RangeInclusive<u32>; 17] = [
+    0x1100..=0x115F,   // Hangul Jamo initials
+    0x2E80..=0x303E,   // CJK radicals, punctuation
+    0x3041..=0x33FF,   // Kana, Bopomofo, CJK compatibility
+    0x3400..=0x4DBF,   // CJK extension A
+    0x4E00..=0x9FFF,   // CJK unified ideographs
+    0xA000..=0xA4CF,   // Yi
+    0xAC00..=0xD7A3,   // Hangul syllables
+    0xF900..=0xFAFF,   // CJK compatibility ideographs
+    0xFE30..=0xFE4F,   // CJK compatibility forms
+    0xFF00..=0xFF60,   // Fullwidth forms
+    0xFFE0..=0xFFE6,   // Fullwidth signs
+    0x1F300..=0x1F64F, // Pictographs and emoticons
+    0x1F680..=0x1F6FF, // Transport and map symbols
+    0x1F900..=0x1F9FF, // Supplemental symbols and pictographs
+    0x1FA70..=0x1FAFF, // Symbols and pictographs extended
+    0x20000..=0x2FFFD, // CJK extensions B and later
+    0x30000..=0x3FFFD, // CJK extension G and later
+];
@@ -0,0 +1,108 @@
+use std::ops::{Add, AddAssign, Mul, Neg, Sub, SubAssign};
+
+use serde::{Deserialize, Serialize};
+
+/// A 2D vector for sub-tile positions, velocities and directions. Tiles
+/// are whole-number coordinates; `to_tile` floors a `Vec2` onto one.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct Vec2 {
+    pub x: f64,
+    pub y: f64,
+}
+
+impl Vec2 {
+    pub const ZERO: Vec2 = Vec2 { x: 0.0, y: 0.0 };
+
+    pub const fn new(x: f64, y: f64) -> Self {
+        Vec2 { x, y }
+    }
+
+    pub fn length(self) -> f64 {
+        self.x.hypot(self.y)
+    }
+
+    /// The same direction with length 1, or zero for the zero vector.
+    pub fn normalize(self) -> Self {
+        let length = self.length();
+        if length > 0.0 {
+            self * (1.0 / length)
+        } else {
+            Vec2::ZERO
+        }
+    }
+
+    /// Linearly interpolates from `self` at `t = 0` to `other` at `t = 1`.
+    pub fn lerp(self, other: Vec2, t: f64) -> Self {
+        self + (other - self) * t
+    }
+
+    /// The tile containing this point. Negative coordinates clamp to 0.
+    pub fn to_tile(self) -> (u16, u16) {
+        (self.x.floor() as u16, self.y.floor() as u16)
+    }
+}
+
+impl From<(f64, f64)> for Vec2 {
+    fn from((x, y): (f64, f64)) -> Self {
+        Vec2 { x, y }
+    }
+}
+
+impl From<(u16, u16)> for Vec2 {
+    fn from((x, y): (u16, u16)) -> Self {
+        Vec2 {
+            x: x as f64,
+            y: y as f64,
+        }
+    }
+}
+
+impl From<Vec2> for (u16, u16) {
+    fn from(v: Vec2) -> Self {
+        v.to_tile()
+    }
+}
+
+impl Add for Vec2 {
+    type Output = Vec2;
+
+    fn add(self, other: Vec2) -> Vec2 {
+        Vec2::new(self.x + other.x, self.y + other.y)
+    }
+}
+
+impl Sub for Vec2 {
+    type Output = Vec2;
+
+    fn sub(self, other: Vec2) -> Vec2 {
+        Vec2::new(self.x - other.x, self.y - other.y)
+    }
+}
+
+impl Mul<f64> for Vec2 {
+    type Output = Vec2;
+
+    fn mul(self, scale: f64) -> Vec2 {
+        Vec2::new(self.x * scale, self.y * scale)
+    }
+}
+
+impl Neg for Vec2 {
+    type Output = Vec2;
+
+    fn neg(self) -> Vec2 {
+        Vec2::new(-self.x, -self.y)
+    }
+}
+
+impl AddAssign for Vec2 {
+    fn add_assign(&mut self, other: Vec2) {
+        *self = *self + other;
+    }
+}
+
+impl SubAssign for Vec2 {
+    fn sub_assign(&mut self, other: Vec2) {
+        *self = *self - other;
+    }
+}
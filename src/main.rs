@@ -1,49 +1,111 @@
-use std::{os::linux::raw::stat, vec};
 extern crate engine;
-use crate::engine::{Entity, World};
+use crate::engine::{
+    movement::{EdgeBehavior, Mover, Position},
+    timer::Timer,
+    Directional, Entity, ExitReason, Facing, Health, Map, Solid, World,
+};
 use core::mem::discriminant as tag;
-use crossterm::{cursor::position, event::KeyCode};
+use crossterm::event::KeyCode;
+use std::collections::HashSet;
 const MAP_HEIGHT: u16 = 15;
 const MAP_WIDTH: u16 = 25; // in characters
 const BULLET_SPEED: f64 = 5.0;
 const PLAYER_SPEED: f64 = 4.5; // characters per second
 const PLAYER_RELOAD_TIME: f64 = 0.3;
+const MAX_HP: f64 = 10.0;
+const BULLET_DAMAGE: f64 = 4.0;
+const ENEMY_HP: f64 = 1.0;
+const BARRIER_HP: f64 = 1.0;
+const INVULNERABLE_TIME: f64 = 1.5;
+const SHIP_GLYPHS: Directional<char> = Directional {
+    up: '^',
+    down: 'v',
+    left: '<',
+    right: '>',
+};
 const PLIBBLE_SPEED: f64 = 2.0;
 const PLIBBLER_RELOAD_TIME: f64 = 3.0;
 const PLIBBLER_SPEED: f64 = 1.5;
 const SHOOTLER_SPEED: f64 = 1.0;
 const SHOOTLER_RELOAD_TIME: f64 = 2.0;
+// '#' is a wall and 'B' a barrier; must be MAP_WIDTH x MAP_HEIGHT
+const LEVEL: &str = "
+#########################
+#                       #
+#                       #
+#                       #
+#                       #
+#                       #
+#                       #
+#                       #
+#                       #
+#                       #
+#                       #
+#    B      B      B    #
+#   BBB    BBB    BBB   #
+#                       #
+#########################
+";
 
 fn main() {
     let mut world = World::new(MAP_WIDTH as usize, MAP_HEIGHT as usize);
-    world.add_entity(Ship {
-        position: (12, 13),
-        tilt: (0.0, 0.0),
-        target: (0, 0),
-        reload: PLAYER_RELOAD_TIME,
+    world.input_map.bind("left", KeyCode::Left);
+    world.input_map.bind("left", KeyCode::Char('a'));
+    world.input_map.bind("right", KeyCode::Right);
+    world.input_map.bind("right", KeyCode::Char('d'));
+    world.input_map.bind("shoot", KeyCode::Up);
+    world.input_map.bind("shoot", KeyCode::Char('w'));
+    world.input_map.bind("restart", KeyCode::Char('r'));
+    world.on_setup(setup);
+    world.add_system(|_delta: f64, world: &mut World| {
+        if world.is_action_active("restart") {
+            world.restart();
+        }
     });
+    match world.init() {
+        Ok(ExitReason::GameOver) => println!("Game over"),
+        Ok(_) => {}
+        Err(error) => eprintln!("{error}"),
+    }
+}
+
+fn setup(world: &mut World) {
+    world.add_entity_with(
+        Ship {
+            position: (12, 13),
+            mover: Mover::new(),
+            target: (0, 0),
+            reload: Timer::one_shot(PLAYER_RELOAD_TIME),
+        },
+        (
+            Health::new(MAX_HP).with_grace(INVULNERABLE_TIME),
+            Align {
+                alignment: Alignment::Player,
+            },
+        ),
+    );
     world.add_entity(Plibbler {
         motion: EnemyMotion {
             position: (3, 1),
-            tilt: (0.0, 0.0),
+            mover: Mover::new(),
             target: (1, 0),
             bounds: (1, 11),
         },
-        reload: PLIBBLER_RELOAD_TIME,
+        reload: Timer::new(PLIBBLER_RELOAD_TIME),
     });
     world.add_entity(Plibbler {
         motion: EnemyMotion {
             position: (21, 1),
-            tilt: (0.0, 0.0),
+            mover: Mover::new(),
             target: (-1, 0),
             bounds: (13, 23),
         },
-        reload: PLIBBLER_RELOAD_TIME,
+        reload: Timer::new(PLIBBLER_RELOAD_TIME),
     });
     world.add_entity(Plibble {
         motion: EnemyMotion {
             position: (1, 2),
-            tilt: (0.0, 0.0),
+            mover: Mover::new(),
             target: (1, 0),
             bounds: (1, 11),
         },
@@ -51,7 +113,7 @@ fn main() {
     world.add_entity(Plibble {
         motion: EnemyMotion {
             position: (23, 2),
-            tilt: (0.0, 0.0),
+            mover: Mover::new(),
             target: (-1, 0),
             bounds: (13, 23),
         },
@@ -59,44 +121,36 @@ fn main() {
     world.add_entity(Shootler {
         motion: EnemyMotion {
             position: (23, 2),
-            tilt: (0.0, 0.0),
+            mover: Mover::new(),
             target: (-1, 0),
             bounds: (13, 23),
         },
-        reload: SHOOTLER_RELOAD_TIME,
+        reload: Timer::new(SHOOTLER_RELOAD_TIME),
     });
 
-    build_walls(&mut world);
-
-    world.add_entity(Barrier { position: (4, 12) });
-    world.add_entity(Barrier { position: (5, 12) });
-    world.add_entity(Barrier { position: (6, 12) });
-    world.add_entity(Barrier { position: (11, 12) });
-    world.add_entity(Barrier { position: (12, 12) });
-    world.add_entity(Barrier { position: (13, 12) });
-    world.add_entity(Barrier { position: (18, 12) });
-    world.add_entity(Barrier { position: (19, 12) });
-    world.add_entity(Barrier { position: (20, 12) });
-    world.add_entity(Barrier { position: (5, 11) });
-    world.add_entity(Barrier { position: (12, 11) });
-    world.add_entity(Barrier { position: (19, 11) });
-    let _ = world.init();
-}
-
-fn build_walls(world: &mut World) {
-    for r in 0..MAP_WIDTH {
-        for c in 0..MAP_HEIGHT {
-            if r == 0 || c == 0 || r == MAP_WIDTH - 1 || c == MAP_HEIGHT - 1 {
-                world.add_entity(Wall { position: (r, c) });
-            }
+    let _ = world.load_level(LEVEL, |character, position| match character {
+        '#' => Some(Box::new(Wall {
+            position,
+            glyph: '#',
+        })),
+        'B' => Some(Box::new(Barrier { position })),
+        _ => None,
+    });
+    let walls: HashSet<_> =
+        world.iter_type::<Wall>().map(|(_, x)| x.position).collect();
+    let glyphs: Vec<_> = world
+        .iter_type::<Wall>()
+        .map(|(id, x)| {
+            (id, world.map.autotile(x.position, |p| walls.contains(&p)))
+        })
+        .collect();
+    for (id, glyph) in glyphs {
+        if let Some(wall) = world.get_entity_as_mut::<Wall>(id) {
+            wall.glyph = glyph;
         }
     }
 }
 
-struct Health {
-    hp: f64,
-}
-
 enum Alignment {
     Player = 0,
     Enemy,
@@ -108,25 +162,20 @@ struct Align {
 
 struct Ship {
     position: (u16, u16),
-    tilt: (f64, f64),
+    mover: Mover,
     target: (i8, i8),
-    reload: f64,
+    reload: Timer,
 }
 
 impl Entity for Ship {
-    fn start(&mut self, world: &mut World, id: i64) {
-        world.set_component(id, Health { hp: 10.0 });
-        world.set_component(
-            id,
-            Align {
-                alignment: Alignment::Player,
-            },
-        );
+    fn end(&mut self, world: &mut World, _id: i64) {
+        world.beep();
+        world.exit_with(ExitReason::GameOver);
     }
     fn update(&mut self, delta: f64, world: &mut World, id: i64) {
         let _ = world
             .ui
-            .debug_draw(15, format!("Tilt: {:?}", self.tilt).as_str());
+            .debug_draw(15, format!("Tilt: {:?}", self.mover.tilt).as_str());
         let _ = world.ui.debug_draw(
             16,
             format!("X_Position: {:?}", self.position.0).as_str(),
@@ -141,58 +190,53 @@ impl Entity for Ship {
         let _ = world
             .ui
             .debug_draw(19, format!("Delta: {:?}", delta).as_str());
+        let health = world.get_component_cloned::<Health>(id);
+        let hp = health.map(|x| x.hp);
+        let _ = world.ui.debug_draw(20, format!("HP: {:?}", hp).as_str());
+        let _ = world.ui.draw_bar(
+            (0, MAP_HEIGHT + 6),
+            MAP_WIDTH,
+            health.map_or(0.0, |x| x.fraction()),
+            crossterm::style::Color::Green,
+            crossterm::style::Color::DarkGrey,
+        );
 
-        match world.ui.current_input {
-            Some(KeyCode::Left) => {
-                if self.target.0 == 1 {
-                    self.zero_movement()
-                } else {
-                    self.target = (-1, 0);
-                }
-            }
-            Some(KeyCode::Right) => {
-                if self.target.0 == -1 {
-                    self.zero_movement();
-                } else {
-                    self.target = (1, 0);
-                }
+        if world.is_action_active("left") {
+            if self.target.0 == 1 {
+                self.zero_movement()
+            } else {
+                self.target = (-1, 0);
             }
-
-            Some(KeyCode::Up) => {
-                self.shoot(world);
+        }
+        if world.is_action_active("right") {
+            if self.target.0 == -1 {
+                self.zero_movement();
+            } else {
+                self.target = (1, 0);
             }
-            _ => {}
         }
-
-        match self.target.0 {
-            1 => self.tilt.0 += PLAYER_SPEED * delta,
-            -1 => self.tilt.0 -= PLAYER_SPEED * delta,
-            _ => {}
+        if world.is_action_active("shoot") {
+            self.shoot(world);
         }
 
-        if self.reload > 0.0 {
-            self.reload -= delta;
-        }
+        let step = self.mover.advance(
+            (self.target.0 as f64, 0.0),
+            PLAYER_SPEED,
+            delta,
+        );
+        let to = world.map.step(self.position, step);
+        self.position = world.try_move(id, self.position, to);
 
-        if self.tilt.0 > 1.0 {
-            self.position.0 += 1;
-            self.tilt.0 -= 1.0;
-        } else if self.tilt.0 < -1.0 {
-            self.position.0 -= 1;
-            self.tilt.0 += 1.0;
-        }
+        self.reload.tick(delta);
 
-        self.position.0 = self.position.0.clamp(1, MAP_WIDTH - 2);
-        self.position.1 = self.position.1.clamp(1, MAP_HEIGHT - 2);
+        self.position = world.map.clamp_position(self.position, 1);
 
-        let visual = match self.target.0 {
-            -1 => '<',
-            1 => '>',
-            _ => '^',
-        };
+        let facing =
+            Facing::from_direction((self.target.0, 0)).unwrap_or(Facing::Up);
+        world.set_facing(id, facing);
         world.map.write(
             self.position,
-            visual,
+            *SHIP_GLYPHS.get(facing),
             crossterm::style::Color::Green,
             id,
         );
@@ -201,76 +245,83 @@ impl Entity for Ship {
 
 impl Ship {
     fn zero_movement(&mut self) {
-        self.tilt = (0.0, 0.0);
+        self.mover.reset();
         self.target = (0, 0);
     }
     fn shoot(&mut self, world: &mut World) {
-        if self.reload <= 0.0 {
-            world.add_entity(Bullet {
-                position: (self.position.0, self.position.1 - 1),
-                tilt: (0.0, 0.0),
-                from_player: true,
-                color: crossterm::style::Color::DarkGreen,
-            });
-            self.reload = PLAYER_RELOAD_TIME;
+        if self.reload.is_ready() {
+            Bullet::spawn(
+                world,
+                world.map.step(self.position, (0, -1)),
+                Mover::new(),
+                true,
+                crossterm::style::Color::DarkGreen,
+            );
+            self.reload.reset();
             self.zero_movement();
         }
     }
 }
 
 struct Bullet {
-    position: (u16, u16),
-    tilt: (f64, f64),
+    mover: Mover,
     from_player: bool,
     color: crossterm::style::Color,
 }
 
+impl Bullet {
+    fn spawn(
+        world: &mut World,
+        position: (u16, u16),
+        mover: Mover,
+        from_player: bool,
+        color: crossterm::style::Color,
+    ) {
+        let (x, y) = position;
+        world.add_entity_with(
+            Bullet {
+                mover,
+                from_player,
+                color,
+            },
+            (Position { x, y }, EdgeBehavior::Despawn),
+        );
+    }
+}
+
 impl Entity for Bullet {
     fn update(&mut self, delta: f64, world: &mut World, id: i64) {
-        self.tilt.1 += if self.from_player {
-            -delta * BULLET_SPEED
-        } else {
-            delta * BULLET_SPEED
+        let direction = if self.from_player { -1.0 } else { 1.0 };
+        let step = self.mover.advance((0.0, direction), BULLET_SPEED, delta);
+        // Bullets are stopped by the border walls, and failing that are
+        // despawned at the map's edge.
+        let Some(position) = world.move_by(id, step) else {
+            return;
         };
-        if self.tilt.1 <= -1.0 {
-            self.position.1 -= 1;
-            self.tilt.1 += 1.0;
-        } else if self.tilt.1 >= 1.0 {
-            self.position.1 += 1;
-            self.tilt.1 -= 1.0;
-        }
-        if self.position.1 <= 0 || self.position.1 >= MAP_HEIGHT - 1 {
-            world.remove_entity(id);
-        } else {
-            let mut other_id = id;
-            match world.query_map(self.position).first() {
+        // Entities already updated this frame are in the current
+        // contents; the rest are only in last frame's.
+        let struck = world
+            .entities_at_current(position)
+            .iter()
+            .chain(world.entities_at(position))
+            .copied()
+            .find(|&x| x != id);
+        if let Some(other_id) = struck {
+            let struck_alignment: Option<&mut Align> =
+                world.get_component(other_id);
+            let hits = match struck_alignment {
                 Some(x) => {
-                    other_id = x.id;
-                }
-                None => {}
-            }
-            if other_id == id {
-                world.map.write(self.position, '*', self.color, id);
-            } else {
-                let struck_alignment: Option<&mut Align> =
-                    world.get_component(other_id);
-                match struck_alignment {
-                    Some(x) => {
-                        if (tag(&x.alignment) == tag(&Alignment::Enemy)
-                            && self.from_player)
-                            || (tag(&x.alignment) == tag(&Alignment::Player)
-                                && !self.from_player)
-                        {
-                            world.remove_entity(id);
-                            world.remove_entity(other_id);
-                        }
-                    }
-                    None => {
-                        world.remove_entity(id);
-                        world.remove_entity(other_id);
-                    }
+                    let player = tag(&x.alignment) == tag(&Alignment::Player);
+                    player != self.from_player
                 }
+                None => true,
+            };
+            if hits {
+                world.remove_entity(id);
+                world.damage(other_id, BULLET_DAMAGE);
             }
+        } else {
+            world.map.write(position, '*', self.color, id);
         }
     }
 }
@@ -280,53 +331,59 @@ struct Barrier {
 }
 
 impl Entity for Barrier {
-    fn update(&mut self, _delta: f64, world: &mut World, id: i64) {
-        world.map.write(
-            self.position,
-            '#',
-            crossterm::style::Color::Yellow,
-            id,
-        );
+    fn start(&mut self, world: &mut World, id: i64) {
+        world.set_component(id, Health::new(BARRIER_HP));
+    }
+    fn draw(&self, map: &mut Map, id: i64) {
+        map.write(self.position, '#', crossterm::style::Color::Yellow, id);
     }
 }
 
 struct Wall {
     position: (u16, u16),
+    glyph: char,
 }
 
 impl Entity for Wall {
-    fn update(&mut self, _delta: f64, world: &mut World, id: i64) {
-        world
-            .map
-            .write(self.position, '#', crossterm::style::Color::White, id);
+    fn start(&mut self, world: &mut World, id: i64) {
+        world.set_component(id, Solid);
+    }
+    fn draw(&self, map: &mut Map, id: i64) {
+        map.write(
+            self.position,
+            self.glyph,
+            crossterm::style::Color::White,
+            id,
+        );
     }
 }
 
 struct EnemyMotion {
     position: (u16, u16),
-    tilt: (f64, f64),
+    mover: Mover,
     target: (i8, i8),
     bounds: (u16, u16),
 }
 
 impl EnemyMotion {
     fn update(&mut self, delta: f64, world: &mut World, id: i64, speed: f64) {
-        self.tilt = (
-            self.tilt.0 + self.target.0 as f64 * speed * delta,
-            self.tilt.1 + self.target.1 as f64 * speed * delta,
+        let step = self.mover.advance(
+            (self.target.0 as f64, self.target.1 as f64),
+            speed,
+            delta,
         );
 
-        if self.tilt.0 >= 1.0 {
-            self.tilt.0 -= 1.0;
-            if self.position.0 >= self.bounds.1 {
-                self.target.0 = -1;
-                self.position.1 += 1;
-            } else {
-                self.position.0 += 1;
-            }
-        } else if self.tilt.0 <= -1.0 {
-            self.tilt.0 += 1.0;
-            if self.position.0 <= self.bounds.0 {
+        // Every whole tile is walked, so a fast enemy or a long frame still
+        // covers the distance, turning at the bounds on the way.
+        for _ in 0..step.0.unsigned_abs() {
+            if self.target.0 > 0 {
+                if self.position.0 >= self.bounds.1 {
+                    self.target.0 = -1;
+                    self.position.1 += 1;
+                } else {
+                    self.position.0 += 1;
+                }
+            } else if self.position.0 <= self.bounds.0 {
                 self.target.0 = 1;
                 self.position.1 += 1;
             } else {
@@ -352,6 +409,7 @@ impl Entity for Plibble {
                 alignment: Alignment::Enemy,
             },
         );
+        world.set_component(id, Health::new(ENEMY_HP));
     }
     fn update(&mut self, delta: f64, world: &mut World, id: i64) {
         self.motion.update(delta, world, id, PLIBBLE_SPEED);
@@ -367,7 +425,7 @@ impl Entity for Plibble {
 
 struct Plibbler {
     motion: EnemyMotion,
-    reload: f64,
+    reload: Timer,
 }
 
 impl Entity for Plibbler {
@@ -378,23 +436,21 @@ impl Entity for Plibbler {
                 alignment: Alignment::Enemy,
             },
         );
+        world.set_component(id, Health::new(ENEMY_HP));
     }
     fn update(&mut self, delta: f64, world: &mut World, id: i64) {
         self.motion.update(delta, world, id, PLIBBLER_SPEED);
 
-        if self.reload >= 0.0 {
-            self.reload -= delta;
-        } else {
-            self.reload = PLIBBLER_RELOAD_TIME;
+        if self.reload.tick(delta) {
             world.add_entity(Plibble {
                 motion: EnemyMotion {
                     position: self.motion.position,
-                    tilt: self.motion.tilt,
+                    mover: self.motion.mover,
                     target: self.motion.target,
                     bounds: self.motion.bounds,
                 },
             });
-            self.motion.tilt.0 -= self.motion.target.0 as f64;
+            self.motion.mover.tilt.x -= self.motion.target.0 as f64;
         }
 
         world.map.write(
@@ -408,7 +464,7 @@ impl Entity for Plibbler {
 
 struct Shootler {
     motion: EnemyMotion,
-    reload: f64,
+    reload: Timer,
 }
 
 impl Entity for Shootler {
@@ -419,24 +475,23 @@ impl Entity for Shootler {
                 alignment: Alignment::Enemy,
             },
         );
+        world.set_component(id, Health::new(ENEMY_HP));
     }
     fn update(&mut self, delta: f64, world: &mut World, id: i64) {
         self.motion.update(delta, world, id, SHOOTLER_SPEED);
 
-        if self.reload >= 0.0 {
-            self.reload -= delta;
-        } else {
-            self.reload = SHOOTLER_RELOAD_TIME;
-            world.add_entity(Bullet {
-                position: self.motion.position,
-                tilt: self.motion.tilt,
-                from_player: false,
-                color: crossterm::style::Color::DarkRed,
-            });
-            self.motion.tilt.0 -= self.motion.target.0 as f64;
+        if self.reload.tick(delta) {
+            Bullet::spawn(
+                world,
+                self.motion.position,
+                self.motion.mover,
+                false,
+                crossterm::style::Color::DarkRed,
+            );
+            self.motion.mover.tilt.x -= self.motion.target.0 as f64;
         }
         let mut visual = 'S';
-        if self.reload > SHOOTLER_RELOAD_TIME * 0.9 {
+        if self.reload.remaining() > self.reload.duration() * 0.9 {
             visual = '$';
         }
 
@@ -448,3 +503,51 @@ impl Entity for Shootler {
         );
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use engine::render::BufferRenderer;
+
+    #[test]
+    fn shooting_from_row_0_does_not_panic() {
+        let mut world = World::new_with_renderer(
+            MAP_WIDTH as usize,
+            MAP_HEIGHT as usize,
+            BufferRenderer::new(MAP_WIDTH, MAP_HEIGHT),
+        );
+        let mut ship = Ship {
+            position: (5, 0),
+            mover: Mover::new(),
+            target: (0, 0),
+            reload: Timer::one_shot(0.0),
+        };
+        ship.shoot(&mut world);
+        assert_eq!(world.entity_count(), 1);
+        for _ in 0..10 {
+            world.tick(0.1);
+        }
+        assert_eq!(world.entity_count(), 0);
+    }
+
+    #[test]
+    fn enemies_walk_every_tile_of_a_long_frame() {
+        let mut world = World::new_with_renderer(
+            MAP_WIDTH as usize,
+            MAP_HEIGHT as usize,
+            BufferRenderer::new(MAP_WIDTH, MAP_HEIGHT),
+        );
+        let mut motion = EnemyMotion {
+            position: (3, 1),
+            mover: Mover::new(),
+            target: (1, 0),
+            bounds: (1, 11),
+        };
+        motion.update(1.0, &mut world, 0, 3.0);
+        assert_eq!(motion.position, (6, 1));
+        // Past the bound it drops a row and walks back the rest.
+        motion.update(2.0, &mut world, 0, 4.0);
+        assert_eq!(motion.position, (9, 2));
+        assert_eq!(motion.target, (-1, 0));
+    }
+}